@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use num_bigint::BigInt;
+use crate::diagnostics::{Diagnostic, ErrorKind};
+use crate::instruction_compiler::CompiledProgram;
+use crate::parser::Span;
+use crate::virtual_machine::{BoolValue, FloatValue, Instruction, IntValue, ObjectCreator, StringValue, ValueType};
+
+/// every .bisc file starts with these
+pub const MAGIC: &[u8; 4] = b"BISC";
+
+/// bump this whenever an instruction, a constant or a section changes how it is written,
+/// older files are then rejected instead of running as something else
+pub const VERSION: u32 = 1;
+
+/// Layout, numbers are little endian and every length or location is a u64:
+///
+/// magic, version u32, then the source files, the instructions, the proseso locations,
+/// the klase definitions and one span per instruction. A string is its byte length and then
+/// its utf-8, maps are written sorted by name so the same program always gives the same bytes.
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.usize(bytes.len());
+        self.bytes.extend_from_slice(bytes);
+    }
+    fn string(&mut self, string: &str) {
+        self.bytes(string.as_bytes());
+    }
+
+    fn constant(&mut self, value: &ValueType) -> Result<(), Diagnostic> {
+        match value {
+            ValueType::Int(IntValue::Int8(int)) => { self.u8(0); self.u8(*int as u8) }
+            ValueType::Int(IntValue::Int16(int)) => { self.u8(1); self.bytes.extend_from_slice(&int.to_le_bytes()) }
+            ValueType::Int(IntValue::Int32(int)) => { self.u8(2); self.u32(*int as u32) }
+            ValueType::Int(IntValue::Int64(int)) => { self.u8(3); self.u64(*int as u64) }
+            ValueType::Int(IntValue::BigInt(int)) => { self.u8(4); self.bytes(&int.to_signed_bytes_le()) }
+            ValueType::Float(FloatValue::Float32(float)) => { self.u8(5); self.u32(float.to_bits()) }
+            ValueType::Float(FloatValue::Float64(float)) => { self.u8(6); self.u64(float.to_bits()) }
+            ValueType::Bool(bool) => { self.u8(7); self.u8(bool.value() as u8) }
+            ValueType::String(string) => { self.u8(8); self.string(string.as_str()) }
+            ValueType::Wala => self.u8(9),
+            // the compiler builds lists and maps with BuildList and BuildMap, it never pushes one whole
+            ValueType::Vector(_) | ValueType::Map(_) => {
+                return Err(Diagnostic::new(ErrorKind::BytecodeSave("a list or map constant can't be saved".to_string()), None))
+            }
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) -> Result<(), Diagnostic> {
+        let simple = |opcode: u8| (opcode, None, None);
+        let (opcode, name, number) = match instruction {
+            Instruction::Add => simple(0),
+            Instruction::Sub => simple(1),
+            Instruction::Mul => simple(2),
+            Instruction::Div => simple(3),
+            Instruction::Mod => simple(4),
+            Instruction::FloorDiv => simple(5),
+            Instruction::Pow => simple(6),
+            Instruction::Neg => simple(7),
+            Instruction::BitAnd => simple(8),
+            Instruction::BitOr => simple(9),
+            Instruction::BitXor => simple(10),
+            Instruction::Shl => simple(11),
+            Instruction::Shr => simple(12),
+            Instruction::BitNot => simple(13),
+            Instruction::Eq => simple(14),
+            Instruction::Neq => simple(15),
+            Instruction::Lt => simple(16),
+            Instruction::Gt => simple(17),
+            Instruction::LtEq => simple(18),
+            Instruction::GtEq => simple(19),
+            Instruction::Load(name) => (20, Some(name), None),
+            Instruction::Store(name) => (21, Some(name), None),
+            Instruction::NewVariable(name) => (22, Some(name), None),
+            Instruction::Delete(name) => (23, Some(name), None),
+            Instruction::Jump(location) => (24, None, Some(*location)),
+            Instruction::JumpIfFalse(location) => (25, None, Some(*location)),
+            Instruction::JumpIfTrue(location) => (26, None, Some(*location)),
+            Instruction::Call(name) => (27, Some(name), None),
+            Instruction::CallMethod(name) => (28, Some(name), None),
+            Instruction::Index => simple(29),
+            Instruction::StoreIndex => simple(30),
+            Instruction::BuildList(length) => (31, None, Some(*length)),
+            Instruction::BuildMap(length) => (32, None, Some(*length)),
+            Instruction::Iterable => simple(33),
+            Instruction::Unpack(count) => (34, None, Some(*count)),
+            Instruction::TryBegin(location) => (35, None, Some(*location)),
+            Instruction::TryEnd => simple(36),
+            Instruction::Throw => simple(37),
+            Instruction::Push(value) => {
+                self.u8(38);
+                return self.constant(value)
+            }
+            Instruction::Pop => simple(39),
+            Instruction::Nop => simple(40),
+            Instruction::Return => simple(41),
+            Instruction::Clone => simple(42),
+            Instruction::Swap => simple(43),
+            Instruction::Rotate => simple(44),
+            Instruction::MoveBack(amount) => (45, None, Some(*amount)),
+            Instruction::LoadMember(name) => (46, Some(name), None),
+        };
+        self.u8(opcode);
+        if let Some(name) = name {
+            self.string(name);
+        }
+        if let Some(number) = number {
+            self.usize(number);
+        }
+        Ok(())
+    }
+
+    fn span(&mut self, span: &Span) {
+        self.usize(span.line);
+        self.usize(span.column);
+        self.u32(span.start);
+        self.u32(span.end);
+        self.u32(span.file);
+    }
+}
+
+/// turns a compiled program and the source files it came from into the bytes of a .bisc file
+pub fn save(program: &CompiledProgram, files: &[PathBuf]) -> Result<Vec<u8>, Diagnostic> {
+    let (instructions, functions, classes, source_map) = program;
+    let mut writer = Writer { bytes: MAGIC.to_vec() };
+    writer.u32(VERSION);
+
+    writer.usize(files.len());
+    for file in files {
+        writer.string(&file.to_string_lossy());
+    }
+    writer.usize(instructions.len());
+    for instruction in instructions {
+        writer.instruction(instruction)?;
+    }
+    let mut functions: Vec<_> = functions.iter().collect();
+    functions.sort();
+    writer.usize(functions.len());
+    for (name, location) in functions {
+        writer.string(name);
+        writer.usize(*location);
+    }
+    let mut classes: Vec<_> = classes.iter().collect();
+    classes.sort_by_key(|(name, _)| *name);
+    writer.usize(classes.len());
+    for (name, class) in classes {
+        writer.string(name);
+        writer.string(class.name());
+        writer.usize(class.members().len());
+        for member in class.members() {
+            writer.string(member);
+        }
+        let mut methods: Vec<_> = class.methods().collect();
+        methods.sort();
+        writer.usize(methods.len());
+        for (method, location) in methods {
+            writer.string(method);
+            writer.usize(*location);
+        }
+    }
+    writer.usize(source_map.len());
+    for span in source_map {
+        writer.span(span);
+    }
+    Ok(writer.bytes)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+fn invalid(problem: impl Into<String>) -> Diagnostic {
+    Diagnostic::new(ErrorKind::BytecodeFile(problem.into()), None)
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], Diagnostic> {
+        let end = self.position.checked_add(count).filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("it ends too early"))?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+    fn u8(&mut self) -> Result<u8, Diagnostic> {
+        Ok(self.take(1)?[0])
+    }
+    fn u32(&mut self) -> Result<u32, Diagnostic> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn u64(&mut self) -> Result<u64, Diagnostic> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    fn usize(&mut self) -> Result<usize, Diagnostic> {
+        usize::try_from(self.u64()?).map_err(|_| invalid("a number in it is too big"))
+    }
+    /// a count of things that each take at least one byte, so a broken count can't ask for more memory than the file has
+    fn count(&mut self) -> Result<usize, Diagnostic> {
+        let count = self.usize()?;
+        if count > self.bytes.len() - self.position {
+            return Err(invalid("it ends too early"))
+        }
+        Ok(count)
+    }
+    fn bytes(&mut self) -> Result<&'a [u8], Diagnostic> {
+        let length = self.usize()?;
+        self.take(length)
+    }
+    fn string(&mut self) -> Result<String, Diagnostic> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| invalid("a name in it is not valid utf-8"))
+    }
+
+    fn constant(&mut self) -> Result<ValueType, Diagnostic> {
+        Ok(match self.u8()? {
+            0 => ValueType::Int(IntValue::Int8(self.u8()? as i8)),
+            1 => ValueType::Int(IntValue::Int16(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))),
+            2 => ValueType::Int(IntValue::Int32(self.u32()? as i32)),
+            3 => ValueType::Int(IntValue::Int64(self.u64()? as i64)),
+            4 => ValueType::Int(IntValue::BigInt(BigInt::from_signed_bytes_le(self.bytes()?))),
+            5 => ValueType::Float(FloatValue::Float32(f32::from_bits(self.u32()?))),
+            6 => ValueType::Float(FloatValue::Float64(f64::from_bits(self.u64()?))),
+            7 => ValueType::Bool(BoolValue::new(self.u8()? != 0)),
+            8 => ValueType::String(StringValue::new(self.string()?)),
+            9 => ValueType::Wala,
+            tag => return Err(invalid(format!("it has an unknown kind of constant {}", tag))),
+        })
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, Diagnostic> {
+        Ok(match self.u8()? {
+            0 => Instruction::Add,
+            1 => Instruction::Sub,
+            2 => Instruction::Mul,
+            3 => Instruction::Div,
+            4 => Instruction::Mod,
+            5 => Instruction::FloorDiv,
+            6 => Instruction::Pow,
+            7 => Instruction::Neg,
+            8 => Instruction::BitAnd,
+            9 => Instruction::BitOr,
+            10 => Instruction::BitXor,
+            11 => Instruction::Shl,
+            12 => Instruction::Shr,
+            13 => Instruction::BitNot,
+            14 => Instruction::Eq,
+            15 => Instruction::Neq,
+            16 => Instruction::Lt,
+            17 => Instruction::Gt,
+            18 => Instruction::LtEq,
+            19 => Instruction::GtEq,
+            20 => Instruction::Load(self.string()?),
+            21 => Instruction::Store(self.string()?),
+            22 => Instruction::NewVariable(self.string()?),
+            23 => Instruction::Delete(self.string()?),
+            24 => Instruction::Jump(self.usize()?),
+            25 => Instruction::JumpIfFalse(self.usize()?),
+            26 => Instruction::JumpIfTrue(self.usize()?),
+            27 => Instruction::Call(self.string()?),
+            28 => Instruction::CallMethod(self.string()?),
+            29 => Instruction::Index,
+            30 => Instruction::StoreIndex,
+            31 => Instruction::BuildList(self.usize()?),
+            32 => Instruction::BuildMap(self.usize()?),
+            33 => Instruction::Iterable,
+            34 => Instruction::Unpack(self.usize()?),
+            35 => Instruction::TryBegin(self.usize()?),
+            36 => Instruction::TryEnd,
+            37 => Instruction::Throw,
+            38 => Instruction::Push(self.constant()?),
+            39 => Instruction::Pop,
+            40 => Instruction::Nop,
+            41 => Instruction::Return,
+            42 => Instruction::Clone,
+            43 => Instruction::Swap,
+            44 => Instruction::Rotate,
+            45 => Instruction::MoveBack(self.usize()?),
+            46 => Instruction::LoadMember(self.string()?),
+            opcode => return Err(invalid(format!("it has an unknown instruction {}", opcode))),
+        })
+    }
+
+    fn span(&mut self) -> Result<Span, Diagnostic> {
+        Ok(Span { line: self.usize()?, column: self.usize()?, start: self.u32()?, end: self.u32()?, file: self.u32()? })
+    }
+}
+
+/// reads a .bisc file back into the program and the source files it was built from,
+/// the program still has to go through the verifier before it runs
+pub fn load(bytes: &[u8]) -> Result<(CompiledProgram, Vec<PathBuf>), Diagnostic> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+        return Err(invalid("it is not a compiled BisayaLang program"))
+    }
+    let version = reader.u32()?;
+    if version != VERSION {
+        return Err(Diagnostic::new(ErrorKind::BytecodeVersion { found: version, expected: VERSION }, None))
+    }
+
+    let files = (0..reader.count()?).map(|_| reader.string().map(PathBuf::from)).collect::<Result<Vec<_>, _>>()?;
+    let instructions = (0..reader.count()?).map(|_| reader.instruction()).collect::<Result<Vec<_>, _>>()?;
+    let mut functions = HashMap::new();
+    for _ in 0..reader.count()? {
+        functions.insert(reader.string()?, reader.usize()?);
+    }
+    let mut classes = HashMap::new();
+    for _ in 0..reader.count()? {
+        let key = reader.string()?;
+        let mut class = ObjectCreator::new(reader.string()?);
+        for _ in 0..reader.count()? {
+            class.add_member(reader.string()?);
+        }
+        for _ in 0..reader.count()? {
+            class.add_method(reader.string()?, reader.usize()?);
+        }
+        classes.insert(key, class);
+    }
+    let source_map = (0..reader.count()?).map(|_| reader.span()).collect::<Result<Vec<_>, _>>()?;
+    if reader.position != bytes.len() {
+        return Err(invalid("it has extra bytes at the end"))
+    }
+    if source_map.len() != instructions.len() {
+        return Err(invalid("its source lines don't match its instructions"))
+    }
+    if source_map.iter().any(|span| span.file as usize >= files.len().max(1)) {
+        return Err(invalid("it points into a source file it doesn't list"))
+    }
+    Ok(((instructions, functions, classes, source_map), files))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::diagnostics::Language;
+    use crate::instruction_compiler;
+    use crate::parser::parse_file_data;
+    use crate::virtual_machine::VirtualMachine;
+    use super::*;
+
+    const SOURCE: &str = r#"
+klase Tao {
+    pangalan,
+    edad
+    proseso ngalan() {
+        balik f"{kani.pangalan} ñ"
+    }
+}
+proseso wala_balik() {
+    deklara x = 1.5
+}
+deklara big = 123456789012345678901234567890
+deklara tao = Tao("Ana", 30)
+assert(tao.ngalan() == "Ana ñ" ug wala_balik() == wala ug big > 2 ** 64 ug 300 + 70000 == 70300 ug true, "the loaded program runs")
+"#;
+
+    fn compiled() -> (CompiledProgram, Vec<PathBuf>) {
+        let mut files = vec![];
+        let program = instruction_compiler::compile(parse_file_data(SOURCE).unwrap(), Path::new("test.bis"), &mut files).unwrap();
+        (program, files)
+    }
+
+    fn load_error(bytes: &[u8]) -> String {
+        load(bytes).expect_err("the bytes should be rejected").kind.message(Language::English)
+    }
+
+    #[test]
+    fn save_then_load_gives_back_the_same_program() {
+        let (program, files) = compiled();
+        let bytes = save(&program, &files).unwrap();
+        let (loaded, loaded_files) = load(&bytes).unwrap();
+        assert_eq!(loaded_files, files);
+        assert_eq!(format!("{:?}", loaded.0), format!("{:?}", program.0), "instructions and their constants");
+        assert_eq!(loaded.1, program.1, "proseso locations");
+        let class = |program: &CompiledProgram| {
+            let class = &program.2["Tao"];
+            let mut methods: Vec<_> = class.methods().map(|(name, location)| (name.clone(), *location)).collect();
+            methods.sort();
+            (program.2.len(), class.name().to_string(), class.members().to_vec(), methods)
+        };
+        assert_eq!(class(&loaded), class(&program));
+        assert_eq!(loaded.3, program.3, "source map");
+        assert_eq!(save(&loaded, &loaded_files).unwrap(), bytes, "saving again gives the same bytes");
+
+        let (instructions, functions, classes, source_map) = loaded;
+        VirtualMachine::new(instructions, functions, classes, source_map).run().unwrap();
+    }
+
+    #[test]
+    fn load_rejects_broken_files() {
+        let (program, files) = compiled();
+        let bytes = save(&program, &files).unwrap();
+
+        assert_eq!(load_error(b"BISX\x01\0\0\0"), "Cannot load .bisc file, it is not a compiled BisayaLang program");
+        assert_eq!(load_error(b"BI"), "Cannot load .bisc file, it is not a compiled BisayaLang program");
+        let mut newer = bytes.clone();
+        newer[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(load(&newer).unwrap_err().kind, ErrorKind::BytecodeVersion { found, expected: VERSION } if found == VERSION + 1));
+        assert_eq!(load_error(&bytes[..bytes.len() - 1]), "Cannot load .bisc file, it ends too early");
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(load_error(&longer), "Cannot load .bisc file, it has extra bytes at the end");
+        let mut huge_count = bytes[..8].to_vec();
+        huge_count.extend_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(load_error(&huge_count), "Cannot load .bisc file, it ends too early");
+    }
+
+    #[test]
+    fn load_never_panics_on_cut_or_changed_bytes() {
+        let (program, files) = compiled();
+        let bytes = save(&program, &files).unwrap();
+        for length in 0..bytes.len() {
+            assert!(load(&bytes[..length]).is_err(), "cut to {} bytes", length);
+        }
+        for index in 0..bytes.len() {
+            let mut changed = bytes.clone();
+            changed[index] ^= 0xff;
+            let _ = load(&changed);
+        }
+    }
+}
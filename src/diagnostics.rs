@@ -0,0 +1,493 @@
+use std::path::Path;
+use pest::error::{Error, ErrorVariant, InputLocation, LineColLocation};
+use crate::format_spec::{FormatError, MAX_WIDTH};
+use crate::parser::{Rule, Span};
+use crate::virtual_machine::RuntimeError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Bisaya,
+    English,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    // parse errors
+    Syntax { expected: Vec<String> },
+    InvalidVariableName(String),
+    // compile errors
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    ReturnOutsideFunction,
+    AlreadyDefined(String),
+    ModuleNotFound(String),
+    /// the chain of modules that leads back to the first one
+    ImportCycle(Vec<String>),
+    NotAllowedInModule,
+    /// the verifier found compiled instructions the virtual machine can't run safely
+    InvalidBytecode(String),
+    /// a .bisc file that is not one, or is cut short
+    BytecodeFile(String),
+    /// a compiled program that can't be written out as a .bisc file
+    BytecodeSave(String),
+    /// a .bisc file written by a different format version than this build reads
+    BytecodeVersion { found: u32, expected: u32 },
+    // runtime errors
+    UndefinedVariable(String),
+    /// a deklara of a name that is already in the same scope
+    AlreadyDeclared(String),
+    FunctionNotFound(String),
+    InvalidOperation { operation: &'static str, lhs: &'static str, rhs: &'static str },
+    /// - or ~ on a value it doesn't work on
+    InvalidUnaryOperation { operation: &'static str, value: &'static str },
+    NegativeShift,
+    ConditionNotBool(&'static str),
+    IndexOutOfRange { index: i64, length: usize },
+    InvalidIndex(&'static str),
+    NotIndexable(&'static str),
+    NotIterable(&'static str),
+    InvalidMapKey(&'static str),
+    KeyNotFound(String),
+    CannotUnpack { value: &'static str, count: usize },
+    UnpackCount { length: usize, count: usize },
+    /// an object where only a plain value works, with the name of its klase
+    ObjectAsValue(String),
+    NoMembers(&'static str),
+    MemberNotFound { class: String, member: String },
+    MethodNotFound { class: String, method: String },
+    CannotCallMethod { method: String, value: &'static str },
+    /// a call with the wrong number of arguments, `max` is None when there is no upper limit,
+    /// the name is a boxed str to keep errors small
+    ArgumentCount { function: Box<str>, min: usize, max: Option<usize>, given: usize },
+    /// a call with an argument of the wrong type, `expected` lists every type that works
+    ArgumentType { function: Box<str>, expected: &'static [&'static str], found: &'static str },
+    EmptyList(String),
+    EmptySeparator,
+    RandomRange { low: i64, high: i64 },
+    AssertionFailed(String),
+    Format(FormatError),
+    DivisionByZero,
+    IntegerTooBig,
+    /// an ilabay that no dakpa caught, with the message of the error
+    Thrown(String),
+    /// the compiled program left less on the stack than an instruction takes off it
+    StackUnderflow,
+    /// a call found something other than its argument count on top of the stack
+    MissingArgumentCount,
+    /// the debug checks found a different number of values on the stack than the compiled program should leave there
+    UnbalancedStack { expected: usize, found: usize },
+}
+
+/// an error from any stage of running a file, ready to be shown to the user
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub kind: ErrorKind,
+    pub span: Option<Span>,
+    pub trace: Vec<(String, Option<Span>)>,
+}
+
+fn type_name(name: &str, language: Language) -> &str {
+    match (language, name) {
+        (Language::English, _) => name,
+        (Language::Bisaya, "int") => "number",
+        (Language::Bisaya, "float") => "decimal",
+        (Language::Bisaya, "string") => "letters",
+        (Language::Bisaya, "list") => "lista",
+        (Language::Bisaya, "map") => "mapa",
+        (Language::Bisaya, name) => name,
+    }
+}
+
+fn operation_verb(operation: &str, language: Language) -> &str {
+    match (language, operation) {
+        (Language::English, "add") => "add",
+        (Language::English, "sub") => "subtract",
+        (Language::English, "mul") => "multiply",
+        (Language::English, "div") => "divide",
+        (Language::English, "mod") => "modulo",
+        (Language::English, "floordiv") => "floor divide",
+        (Language::English, "pow") => "exponentiate",
+        (Language::English, "bitand") => "bitwise and",
+        (Language::English, "bitor") => "bitwise or",
+        (Language::English, "bitxor") => "bitwise xor",
+        (Language::English, "shl") | (Language::English, "shr") => "shift",
+        (Language::English, _) => "compare",
+        (Language::Bisaya, "add") => "add",
+        (Language::Bisaya, "sub") => "minus",
+        (Language::Bisaya, "mul") => "multiply",
+        (Language::Bisaya, "div") => "divide",
+        (Language::Bisaya, "mod") => "modulo",
+        (Language::Bisaya, "floordiv") => "floor divide",
+        (Language::Bisaya, "pow") => "power",
+        (Language::Bisaya, "bitand") => "bitwise and",
+        (Language::Bisaya, "bitor") => "bitwise or",
+        (Language::Bisaya, "bitxor") => "bitwise xor",
+        (Language::Bisaya, "shl") | (Language::Bisaya, "shr") => "i-shift",
+        (Language::Bisaya, _) => "ikumpara",
+    }
+}
+
+fn unary_verb(operation: &str, language: Language) -> &str {
+    match (language, operation) {
+        (Language::English, "neg") => "negate",
+        (Language::English, _) => "invert the bits of",
+        (Language::Bisaya, "neg") => "i-negative",
+        (Language::Bisaya, _) => "i-bitwise not",
+    }
+}
+
+/// "list or string", with the type names in the language of the message
+fn type_list(types: &[&str], language: Language) -> String {
+    let names: Vec<&str> = types.iter().map(|name| type_name(name, language)).collect();
+    names.join(match language {
+        Language::Bisaya => " o ",
+        Language::English => " or ",
+    })
+}
+
+/// how many arguments a call takes, "1 or 2 arguments" or "1 o 2 ka argument"
+fn argument_count(min: usize, max: Option<usize>, language: Language) -> String {
+    match (language, max) {
+        (Language::English, Some(0)) => "no arguments".to_string(),
+        (Language::English, Some(1)) if min == 1 => "1 argument".to_string(),
+        (Language::English, Some(max)) if max == min => format!("{} arguments", min),
+        (Language::English, Some(max)) => format!("{} or {} arguments", min, max),
+        (Language::English, None) => format!("{} or more arguments", min),
+        (Language::Bisaya, Some(0)) => "walay argument".to_string(),
+        (Language::Bisaya, Some(max)) if max == min => format!("{} ka argument", min),
+        (Language::Bisaya, Some(max)) => format!("{} o {} ka argument", min, max),
+        (Language::Bisaya, None) => format!("{} o labaw pa ka argument", min),
+    }
+}
+
+fn format_error(error: &FormatError, language: Language) -> String {
+    match language {
+        Language::Bisaya => match error {
+            FormatError::UnclosedBrace => "Ang format string naay { nga wala gisirado, gamita ang {{ para sa { mismo.".to_string(),
+            FormatError::UnopenedBrace => "Ang format string naay } nga wala giablihi, gamita ang }} para sa } mismo.".to_string(),
+            FormatError::InvalidPlaceholder(placeholder) => format!("Dili sakto ang placeholder nga {{{}}} sa format.", placeholder),
+            FormatError::ArgumentTooBig(index) => format!("Sobra ka dako ang numero sa argument nga {} sa format.", index),
+            FormatError::MissingPrecision(spec) => format!("Ang format spec nga '{}' naay . pero walay precision human niini.", spec),
+            FormatError::InvalidSpec(spec) => format!("Dili sakto ang format spec nga '{}'.", spec),
+            FormatError::WidthTooBig(spec) => format!(
+                "Sobra ka dako ang gilapdon o precision sa format spec nga '{}', {} ra ang pinakadako.", spec, MAX_WIDTH
+            ),
+            FormatError::PrecisionOnInt(spec) => format!(
+                "Ang format spec nga '{}' naay precision, pero walay decimal ang x, X, o ug b nga maputol.", spec
+            ),
+            FormatError::NotAnInt { kind, value } => format!("Ang format spec nga {} kay para ra sa number, {} ang imong gihatag.", kind, value),
+            FormatError::NotANumber(value) => format!("Ang format spec nga f kay para ra sa number o decimal, {} ang imong gihatag.", value),
+            FormatError::UnknownName(name) => format!("Walay argument nga ginganlan ug '{}' sa format.", name),
+            FormatError::MixedPlaceholders => "Dili pwede isagol sa format ang {} ug ang placeholder nga naay numero sama sa {0}.".to_string(),
+            FormatError::MissingArgument { needed, given } => format!(
+                "Nagkinahanglan ang format ug {} ka argument pero {} ra ang gihatag.", needed, given
+            ),
+            FormatError::UnusedArguments { given, used } => format!(
+                "{} ka argument ang gihatag sa format pero {} ra ang nagamit.", given, used
+            ),
+        },
+        Language::English => match error {
+            FormatError::UnclosedBrace => "format string has a { that is never closed, use {{ for a literal {".to_string(),
+            FormatError::UnopenedBrace => "format string has a } that was never opened, use }} for a literal }".to_string(),
+            FormatError::InvalidPlaceholder(placeholder) => format!("format placeholder {{{}}} is not valid", placeholder),
+            FormatError::ArgumentTooBig(index) => format!("format argument {} is too big", index),
+            FormatError::MissingPrecision(spec) => format!("format spec '{}' has a . without a precision after it", spec),
+            FormatError::InvalidSpec(spec) => format!("format spec '{}' is not valid", spec),
+            FormatError::WidthTooBig(spec) => format!("format spec '{}' asks for a width or precision over {}", spec, MAX_WIDTH),
+            FormatError::PrecisionOnInt(spec) => format!("format spec '{}' has a precision, x, X, o and b can't take one", spec),
+            FormatError::NotAnInt { kind, value } => format!("format spec {} only works on an int, got {}", kind, value),
+            FormatError::NotANumber(value) => format!("format spec f only works on a number, got {}", value),
+            FormatError::UnknownName(name) => format!("format has no argument named '{}'", name),
+            FormatError::MixedPlaceholders => "format cannot mix {} with numbered placeholders like {0}".to_string(),
+            FormatError::MissingArgument { needed, given } => format!(
+                "format string needs at least {} arguments but only {} given", needed, given
+            ),
+            FormatError::UnusedArguments { given, used } => format!(
+                "format was given {} arguments but only {} are used", given, used
+            ),
+        },
+    }
+}
+
+impl ErrorKind {
+    pub fn message(&self, language: Language) -> String {
+        match language {
+            Language::Bisaya => match self {
+                ErrorKind::Syntax { expected } if expected.is_empty() => "Wala ko kasabot sa imong gisulat diri.".to_string(),
+                ErrorKind::Syntax { expected } => format!("Wala ko kasabot sa imong gisulat diri,\nnaghulat ko ug {}.", expected.join(" o ")),
+                ErrorKind::InvalidVariableName(name) => format!("Dili pwede naay tuldok (.) ang pangalan sa variable nga '{}'.", name),
+                ErrorKind::BreakOutsideLoop => "Ang 'buwag' kay pwede ra gamiton sulod sa loop.".to_string(),
+                ErrorKind::ContinueOutsideLoop => "Ang 'tiwas' kay pwede ra gamiton sulod sa loop.".to_string(),
+                ErrorKind::ReturnOutsideFunction => "Ang 'balik' kay pwede ra gamiton sulod sa proseso.".to_string(),
+                ErrorKind::AlreadyDefined(name) => format!("Naa nay proseso o klase nga ginganlan ug '{}'.", name),
+                ErrorKind::ModuleNotFound(path) => format!("Wala ko kakita sa module nga '{}'.", path),
+                ErrorKind::ImportCycle(chain) => format!("Nag-libot ang pag ipasulod: {}.", chain.join(" -> ")),
+                ErrorKind::NotAllowedInModule => "Proseso, klase ug ipasulod ra ang pwede sa module.".to_string(),
+                ErrorKind::InvalidBytecode(problem) => format!("Guba ang na-compile nga programa, {}.", problem),
+                ErrorKind::BytecodeFile(problem) => format!("Dili mabasa ang .bisc nga file, {}.", problem),
+                ErrorKind::BytecodeSave(problem) => format!("Dili ma-save ang .bisc nga file, {}.", problem),
+                ErrorKind::BytecodeVersion { found, expected } => format!(
+                    "Ang .bisc nga file kay bersyon {} pero bersyon {} ang kaya nako,\ni-build usab ang .bis nga file.", found, expected
+                ),
+                ErrorKind::UndefinedVariable(name) => format!("Ayaw ug pag binugo,\nwala pa nimo gideklara ang '{}'.", name),
+                ErrorKind::AlreadyDeclared(name) => format!("Ayaw ug pag binugo,\nnadeklara na nimo ang '{}'.", name),
+                ErrorKind::FunctionNotFound(name) => format!("Ayaw ug pag binugo,\nwalay proseso nga ginganlan ug '{}'.", name),
+                ErrorKind::InvalidOperation { operation, lhs, rhs } => format!(
+                    "Ayaw ug pag binugo,\nikaw daw {} ug {} sa {}.",
+                    operation_verb(operation, language), type_name(lhs, language), type_name(rhs, language)
+                ),
+                ErrorKind::InvalidUnaryOperation { operation, value } => format!(
+                    "Ayaw ug pag binugo,\ndili pwede {} ang {}.", unary_verb(operation, language), type_name(value, language)
+                ),
+                ErrorKind::NegativeShift => "Ayaw ug pag binugo,\ndili pwede negative ang gidaghanon sa shift.".to_string(),
+                ErrorKind::ConditionNotBool(found) => format!(
+                    "Ayaw ug pag binugo,\nbool dapat ang kondisyon, {} ang imong gihatag.", type_name(found, language)
+                ),
+                ErrorKind::IndexOutOfRange { index, length } => format!(
+                    "Ayaw ug pag binugo,\nwalay ika {} sa lista nga {} ra ka butang.", index, length
+                ),
+                ErrorKind::InvalidIndex(found) => format!(
+                    "Ayaw ug pag binugo,\nnumber ra ang pwede nga index sa lista, {} ang imong gihatag.", type_name(found, language)
+                ),
+                ErrorKind::NotIndexable(value) => format!("Ayaw ug pag binugo,\ndili pwede i-index ang {}.", type_name(value, language)),
+                ErrorKind::NotIterable(value) => format!("Ayaw ug pag binugo,\ndili pwede i-loop ang {}.", type_name(value, language)),
+                ErrorKind::InvalidMapKey(found) => format!(
+                    "Ayaw ug pag binugo,\nnumber, bool o letters ra ang pwede nga key sa mapa, {} ang imong gihatag.", type_name(found, language)
+                ),
+                ErrorKind::KeyNotFound(key) => format!("Ayaw ug pag binugo,\nwala ang '{}' sa mapa.", key),
+                ErrorKind::CannotUnpack { value, count } => format!(
+                    "Ayaw ug pag binugo,\ndili pwede i-unpack ang {} ngadto sa {} ka variable.", type_name(value, language), count
+                ),
+                ErrorKind::UnpackCount { length, count } => format!(
+                    "Ayaw ug pag binugo,\n{} ka butang ang lista pero {} ka variable ang gusto nimo butangan.", length, count
+                ),
+                ErrorKind::ObjectAsValue(class) => format!("Ayaw ug pag binugo,\nobject nga {} ang naa diri, dili pwede gamiton isip value.", class),
+                ErrorKind::NoMembers(value) => format!("Ayaw ug pag binugo,\nwalay mga field ang {}.", type_name(value, language)),
+                ErrorKind::MemberNotFound { class, member } => format!("Ayaw ug pag binugo,\nwalay '{}' ang {}.", member, class),
+                ErrorKind::MethodNotFound { class, method } => format!(
+                    "Ayaw ug pag binugo,\nwalay proseso nga '{}' ang {}.", method, type_name(class, language)
+                ),
+                ErrorKind::CannotCallMethod { method, value } => format!(
+                    "Ayaw ug pag binugo,\ndili pwede tawagon ang '{}' sa {}.", method, type_name(value, language)
+                ),
+                ErrorKind::ArgumentCount { function, min, max, given } => format!(
+                    "Ayaw ug pag binugo,\n{} ang kinahanglan sa {}, {} ang imong gihatag.", argument_count(*min, *max, language), function, given
+                ),
+                ErrorKind::ArgumentType { function, expected, found } => format!(
+                    "Ayaw ug pag binugo,\n{} ang kinahanglan sa {}, {} ang imong gihatag.",
+                    type_list(expected, language), function, type_name(found, language)
+                ),
+                ErrorKind::EmptyList(function) => format!("Ayaw ug pag binugo,\nwalay sulod ang lista nga gihatag sa {}.", function),
+                ErrorKind::EmptySeparator => "Ayaw ug pag binugo,\ndili pwede walay sulod ang separator sa split.".to_string(),
+                ErrorKind::RandomRange { low, high } => format!("Ayaw ug pag binugo,\nmas dako ang {} kaysa {} sa random.numero.", low, high),
+                ErrorKind::AssertionFailed(message) => format!("Napakyas ang assert: {}", message),
+                ErrorKind::Format(error) => format_error(error, language),
+                ErrorKind::DivisionByZero => "Ayaw ug pag binugo,\ndili pwede i-divide ang number sa zero.".to_string(),
+                ErrorKind::IntegerTooBig => "Sobra ka dako ang number nga gusto nimo buhaton.".to_string(),
+                ErrorKind::Thrown(message) => format!("Walay nakadakpa sa gilabay nga error: {}", message),
+                ErrorKind::StackUnderflow => "Nahurot ang stack sa virtual machine, basin guba ang na-compile nga programa.".to_string(),
+                ErrorKind::MissingArgumentCount => {
+                    "Walay gidaghanon sa mga argument sa stack, basin guba ang na-compile nga programa.".to_string()
+                }
+                ErrorKind::UnbalancedStack { expected, found } => format!(
+                    "{} ka value ang naa sa stack imbes nga {}, basin guba ang na-compile nga programa.", found, expected
+                ),
+            },
+            Language::English => match self {
+                ErrorKind::Syntax { expected } if expected.is_empty() => "Could not parse this".to_string(),
+                ErrorKind::Syntax { expected } => format!("Could not parse this, expected {}", expected.join(" or ")),
+                ErrorKind::InvalidVariableName(name) => format!("Variable name '{}' cannot contain a period .", name),
+                ErrorKind::BreakOutsideLoop => "break statement outside of loop".to_string(),
+                ErrorKind::ContinueOutsideLoop => "continue statement outside of loop".to_string(),
+                ErrorKind::ReturnOutsideFunction => "return statement outside of function".to_string(),
+                ErrorKind::AlreadyDefined(name) => format!("A function or class named '{}' is already defined", name),
+                ErrorKind::ModuleNotFound(path) => format!("Module '{}' not found", path),
+                ErrorKind::ImportCycle(chain) => format!("Circular import: {}", chain.join(" -> ")),
+                ErrorKind::NotAllowedInModule => "A module can only contain proseso, klase and ipasulod".to_string(),
+                ErrorKind::InvalidBytecode(problem) => format!("Invalid compiled program, {}", problem),
+                ErrorKind::BytecodeFile(problem) => format!("Cannot load .bisc file, {}", problem),
+                ErrorKind::BytecodeSave(problem) => format!("Cannot save .bisc file, {}", problem),
+                ErrorKind::BytecodeVersion { found, expected } => format!(
+                    "The .bisc file is format version {} but this build reads version {}, build the .bis file again", found, expected
+                ),
+                ErrorKind::UndefinedVariable(name) => format!("Variable {} not found", name),
+                ErrorKind::AlreadyDeclared(name) => format!("{} already exists", name),
+                ErrorKind::FunctionNotFound(name) => format!("Function {} not found", name),
+                ErrorKind::InvalidOperation { operation, lhs, rhs } => format!(
+                    "Cannot {} {} and {}", operation_verb(operation, language), lhs, rhs
+                ),
+                ErrorKind::InvalidUnaryOperation { operation, value } => format!("Cannot {} {}", unary_verb(operation, language), value),
+                ErrorKind::NegativeShift => "Negative shift count".to_string(),
+                ErrorKind::ConditionNotBool(found) => format!("Expected a bool for the condition, got {}", found),
+                ErrorKind::IndexOutOfRange { index, length } => format!("Index {} is out of range for a list of length {}", index, length),
+                ErrorKind::InvalidIndex(found) => format!("List indices must be int, got {}", found),
+                ErrorKind::NotIndexable(value) => format!("Cannot index into {}", value),
+                ErrorKind::NotIterable(value) => format!("Cannot loop over {}", value),
+                ErrorKind::InvalidMapKey(found) => format!("Map keys must be int, bool or string, got {}", found),
+                ErrorKind::KeyNotFound(key) => format!("Key '{}' is not in the map", key),
+                ErrorKind::CannotUnpack { value, count } => format!("Cannot unpack {} into {} variables", value, count),
+                ErrorKind::UnpackCount { length, count } => format!("Cannot unpack a list of {} into {} variables", length, count),
+                ErrorKind::ObjectAsValue(class) => format!("Expected a value but got an instance of {}", class),
+                ErrorKind::NoMembers(value) => format!("{} has no members", value),
+                ErrorKind::MemberNotFound { class, member } => format!("{} has no member named '{}'", class, member),
+                ErrorKind::MethodNotFound { class, method } => format!("{} has no method named '{}'", class, method),
+                ErrorKind::CannotCallMethod { method, value } => format!("Cannot call method {} on {}", method, value),
+                ErrorKind::ArgumentCount { function, min, max, given } => format!(
+                    "{} takes {}, {} given", function, argument_count(*min, *max, language), given
+                ),
+                ErrorKind::ArgumentType { function, expected, found } => format!(
+                    "{} takes {}, got {}", function, type_list(expected, language), found
+                ),
+                ErrorKind::EmptyList(function) => format!("{} was called on an empty list", function),
+                ErrorKind::EmptySeparator => "split takes a separator that is not empty".to_string(),
+                ErrorKind::RandomRange { low, high } => format!("random.numero was called with {} greater than {}", low, high),
+                ErrorKind::AssertionFailed(message) => format!("Assertion failed: {}", message),
+                ErrorKind::Format(error) => format_error(error, language),
+                ErrorKind::DivisionByZero => "Integer division by zero".to_string(),
+                ErrorKind::IntegerTooBig => "Integer result is too big".to_string(),
+                ErrorKind::Thrown(message) => format!("Uncaught error: {}", message),
+                ErrorKind::StackUnderflow => "Stack underflow".to_string(),
+                ErrorKind::MissingArgumentCount => "Expected an argument count on the stack".to_string(),
+                ErrorKind::UnbalancedStack { expected, found } => format!("Unbalanced stack, expected {} values but found {}", expected, found),
+            },
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn new(kind: ErrorKind, span: Option<Span>) -> Self {
+        Self { kind, span, trace: vec![] }
+    }
+
+    pub fn from_pest(error: Error<Rule>) -> Self {
+        let expected = match &error.variant {
+            ErrorVariant::ParsingError { positives, .. } => positives.iter().map(|rule| format!("{:?}", rule)).collect(),
+            ErrorVariant::CustomError { message } => vec![message.clone()],
+        };
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+        };
+        let (start, end) = match error.location {
+            InputLocation::Pos(pos) => (pos, pos),
+            InputLocation::Span(span) => span,
+        };
+        Self::new(ErrorKind::Syntax { expected }, Some(Span { line, column, start: start as u32, end: end as u32, file: 0 }))
+    }
+
+    /// where to put the caret, undefined variables point at the name instead of the start of the statement
+    fn caret(&self, line: &str, span: Span) -> (usize, usize) {
+        let start = span.column.saturating_sub(1);
+        let statement = line.get(start..).unwrap_or("");
+        if let ErrorKind::UndefinedVariable(name) = &self.kind {
+            let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.';
+            for (index, _) in statement.match_indices(name.as_str()) {
+                let before = statement[..index].chars().next_back();
+                let after = statement[index + name.len()..].chars().next();
+                if !before.is_some_and(is_identifier) && !after.is_some_and(is_identifier) {
+                    return (start + index, name.len());
+                }
+            }
+        }
+        let length = (span.end.saturating_sub(span.start) as usize).min(statement.trim_end().len()).max(1);
+        (start, length)
+    }
+
+    /// `source` is the text of the file the span points into, `file` is its path when that is an imported module
+    pub fn render(&self, source: &str, file: Option<&Path>, language: Language) -> String {
+        let mut output = String::new();
+        if let Some(span) = self.span {
+            let line = source.lines().nth(span.line.saturating_sub(1)).unwrap_or("");
+            let (caret_start, caret_length) = self.caret(line, span);
+            if let Some(file) = file {
+                output += &format!("{}, ", file.display());
+            }
+            match language {
+                Language::Bisaya => output += &format!("linya {}, ika {} na karakter:\n", span.line, caret_start + 1),
+                Language::English => output += &format!("line {}, character {}:\n", span.line, caret_start + 1),
+            }
+            output += &format!("    {}\n", line);
+            output += &format!("    {}{}\n", " ".repeat(caret_start), "^".repeat(caret_length));
+            output += "\n";
+        } else if let Some(file) = file {
+            output += &format!("{}: ", file.display());
+        }
+        output += &self.kind.message(language);
+        for (function_name, call_site) in self.trace.iter().rev() {
+            output += &match (language, call_site) {
+                (Language::Bisaya, Some(span)) => format!("\n    sa proseso {}, gitawag sa linya {}", function_name, span.line),
+                (Language::Bisaya, None) => format!("\n    sa proseso {}", function_name),
+                (Language::English, Some(span)) => format!("\n    in proseso {} called at line {}", function_name, span.line),
+                (Language::English, None) => format!("\n    in proseso {}", function_name),
+            };
+        }
+        output
+    }
+}
+
+impl From<RuntimeError> for Diagnostic {
+    fn from(error: RuntimeError) -> Self {
+        Self { kind: error.kind, span: error.span, trace: error.trace }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_file_data;
+    use super::*;
+
+    const SOURCE: &str = "deklara numero = 1\ndeklara pangalan = \"Name\"\nprintln(numero + pangalang)\n";
+
+    fn undefined() -> Diagnostic {
+        Diagnostic::new(ErrorKind::UndefinedVariable("pangalang".to_string()), Some(Span { line: 3, column: 1, start: 44, end: 71, file: 0 }))
+    }
+
+    #[test]
+    fn renders_in_bisaya_with_a_caret_under_the_name() {
+        assert_eq!(undefined().render(SOURCE, None, Language::Bisaya), concat!(
+            "linya 3, ika 18 na karakter:\n",
+            "    println(numero + pangalang)\n",
+            "                     ^^^^^^^^^\n",
+            "\n",
+            "Ayaw ug pag binugo,\n",
+            "wala pa nimo gideklara ang 'pangalang'.",
+        ));
+    }
+
+    #[test]
+    fn renders_in_english_with_the_module_path() {
+        let rendered = undefined().render(SOURCE, Some(Path::new("lib/utils.bis")), Language::English);
+        assert!(rendered.starts_with("lib/utils.bis, line 3, character 18:\n"), "{}", rendered);
+        assert!(rendered.ends_with("Variable pangalang not found"), "{}", rendered);
+    }
+
+    #[test]
+    fn errors_without_a_span_still_name_the_file() {
+        let diagnostic = Diagnostic::new(ErrorKind::BytecodeFile("it is cut short".to_string()), None);
+        assert_eq!(diagnostic.render("", Some(Path::new("main.bisc")), Language::English), "main.bisc: Cannot load .bisc file, it is cut short");
+    }
+
+    #[test]
+    fn parse_errors_are_diagnostics() {
+        let diagnostic = parse_file_data("deklara x = (1 +\n").unwrap_err();
+        assert!(matches!(diagnostic.kind, ErrorKind::Syntax { .. }));
+        assert_eq!(diagnostic.span.unwrap().line, 1);
+        assert!(diagnostic.render("deklara x = (1 +\n", None, Language::Bisaya).contains("Wala ko kasabot sa imong gisulat diri"));
+    }
+
+    #[test]
+    fn builtin_argument_errors_are_translated() {
+        let kind = ErrorKind::ArgumentType { function: "length".into(), expected: &["list", "map", "string"], found: "int" };
+        assert_eq!(kind.message(Language::Bisaya), "Ayaw ug pag binugo,\nlista o mapa o letters ang kinahanglan sa length, number ang imong gihatag.");
+        assert_eq!(kind.message(Language::English), "length takes list or map or string, got int");
+    }
+
+    #[test]
+    fn trace_lists_the_innermost_call_last() {
+        let mut diagnostic = Diagnostic::new(ErrorKind::DivisionByZero, None);
+        diagnostic.trace = vec![("gawas".to_string(), Some(Span { line: 9, ..Span::default() })), ("sulod".to_string(), None)];
+        assert_eq!(
+            diagnostic.render("", None, Language::English),
+            "Integer division by zero\n    in proseso sulod\n    in proseso gawas called at line 9"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use crate::instruction_compiler::CompiledProgram;
+use crate::parser::Span;
+use crate::virtual_machine::{Instruction, ValueType};
+
+fn jump_target(instruction: &Instruction) -> Option<usize> {
+    match instruction {
+        Instruction::Jump(location) | Instruction::JumpIfFalse(location) |
+        Instruction::JumpIfTrue(location) | Instruction::TryBegin(location) => Some(*location),
+        _ => None,
+    }
+}
+
+fn constant(value: &ValueType) -> String {
+    match value {
+        ValueType::String(string) => format!("{:?}", string.as_str()),
+        value => value.as_string(),
+    }
+}
+
+fn show(instruction: &Instruction, labels: &HashMap<usize, String>) -> String {
+    let label = |location: &usize| labels.get(location).cloned().unwrap_or_else(|| location.to_string());
+    match instruction {
+        Instruction::Load(name) => format!("Load {}", name),
+        Instruction::Store(name) => format!("Store {}", name),
+        Instruction::NewVariable(name) => format!("NewVariable {}", name),
+        Instruction::Delete(name) => format!("Delete {}", name),
+        Instruction::Call(name) => format!("Call {}", name),
+        Instruction::CallMethod(name) => format!("CallMethod {}", name),
+        Instruction::LoadMember(name) => format!("LoadMember {}", name),
+        Instruction::Jump(location) => format!("Jump {}", label(location)),
+        Instruction::JumpIfFalse(location) => format!("JumpIfFalse {}", label(location)),
+        Instruction::JumpIfTrue(location) => format!("JumpIfTrue {}", label(location)),
+        Instruction::TryBegin(location) => format!("TryBegin {}", label(location)),
+        Instruction::BuildList(length) => format!("BuildList {}", length),
+        Instruction::BuildMap(length) => format!("BuildMap {}", length),
+        Instruction::Unpack(count) => format!("Unpack {}", count),
+        Instruction::MoveBack(amount) => format!("MoveBack {}", amount),
+        Instruction::Push(value) => format!("Push {}", constant(value)),
+        instruction => format!("{:?}", instruction),
+    }
+}
+
+/// the file name, line number and text of where an instruction came from
+fn source_line(span: &Span, files: &[PathBuf], sources: &[String]) -> String {
+    let text = sources.get(span.file as usize).and_then(|source| source.lines().nth(span.line.saturating_sub(1)));
+    let file = files.get(span.file as usize)
+        .map_or_else(String::new, |file| format!("{}:", file.file_name().unwrap_or_default().to_string_lossy()));
+    match text {
+        Some(text) => format!("; {}{}  {}", file, span.line, text.trim()),
+        None => format!("; {}{}", file, span.line),
+    }
+}
+
+/// Lists a compiled program one numbered instruction per line, numbered from 1 like the jumps count.
+/// Jump targets get labels, every proseso and method gets a header at its first instruction, and the
+/// source line an instruction came from is shown above the first instruction compiled from it.
+/// `sources` holds the text of each of `files`, a missing one only loses the text of its lines.
+pub fn disassemble(program: &CompiledProgram, files: &[PathBuf], sources: &[String]) -> String {
+    let (instructions, functions, classes, source_map) = program;
+    let end = instructions.len() + 1;
+
+    let targets: BTreeSet<usize> = instructions.iter().filter_map(jump_target).collect();
+    let labels: HashMap<usize, String> = targets.iter().enumerate()
+        .map(|(index, location)| (*location, if *location == end { "end".to_string() } else { format!("L{}", index + 1) }))
+        .collect();
+
+    let mut entries: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for (name, location) in functions {
+        entries.entry(*location).or_default().push(format!("proseso {}", name));
+    }
+    for (class_name, class) in classes {
+        for (method, location) in class.methods() {
+            entries.entry(*location).or_default().push(format!("klase {} proseso {}", class_name, method));
+        }
+    }
+
+    let width = end.to_string().len();
+    let mut output = String::new();
+    let mut previous: Option<Span> = None;
+    for (index, instruction) in instructions.iter().enumerate() {
+        let location = index + 1;
+        if let Some(names) = entries.get_mut(&location) {
+            names.sort();
+            output += &format!("\n{}:\n", names.join(", "));
+            previous = None;
+        }
+        if let Some(label) = labels.get(&location) {
+            output += &format!("{}:\n", label);
+        }
+        if let Some(span) = source_map.get(index) {
+            let same_line = previous.is_some_and(|previous| previous.file == span.file && previous.line == span.line);
+            if !same_line {
+                output += &format!("{:width$}  {}\n", "", source_line(span, files, sources), width = width);
+            }
+            previous = Some(*span);
+        }
+        output += &format!("{:>width$}  {}\n", location, show(instruction, &labels), width = width);
+    }
+    if let Some(label) = labels.get(&end) {
+        output += &format!("{}:\n", label);
+    }
+    output.trim_start().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::instruction_compiler;
+    use crate::parser::parse_file_data;
+    use crate::virtual_machine::{Instruction, IntValue, ValueType};
+    use super::*;
+
+    #[test]
+    fn lists_labels_headers_and_source_lines() {
+        let source = "proseso doble(x) {\n    balik x * 2\n}\ndeklara i = 0\nsamtang i < 3 {\n    i = i + doble(1)\n}\n";
+        let program = instruction_compiler::compile(parse_file_data(source).unwrap(), Path::new("dir/test.bis"), &mut vec![]).unwrap();
+        let listing = disassemble(&program, &[PathBuf::from("dir/test.bis")], &[source.to_string()]);
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert_eq!(lines[..2], ["; test.bis:1  proseso doble(x) {", " 1  Jump L1"], "numbers are right aligned\n{}", listing);
+        let header = lines.iter().position(|line| *line == "proseso doble:").expect(&listing);
+        assert_eq!(lines[header - 1], "", "a blank line before every header");
+        assert_eq!(lines[header + 1].trim(), "; test.bis:1  proseso doble(x) {", "the source line shows again after a header");
+        assert!(lines.contains(&"    ; test.bis:2  balik x * 2"), "{}", listing);
+        assert_eq!(listing.matches("; test.bis:6").count(), 1, "one source line per statement\n{}", listing);
+        for expected in ["L1:", "L2:", "L3:", "Call doble", "Push \"Error: Expected 1 arguments\"", "JumpIfFalse L3"] {
+            assert!(lines.iter().any(|line| line.trim_start().trim_start_matches(char::is_numeric).trim() == expected), "{} in\n{}", expected, listing);
+        }
+    }
+
+    #[test]
+    fn jumps_past_the_last_instruction_go_to_end() {
+        let int = |value| Instruction::Push(ValueType::Int(IntValue::Int32(value)));
+        let program = (vec![int(1), Instruction::JumpIfTrue(3), Instruction::Nop], HashMap::new(), HashMap::new(), vec![Span::default(); 3]);
+        let listing = disassemble(&program, &[], &[]);
+        assert_eq!(listing, "; 0\n1  Push 1\n2  JumpIfTrue L1\nL1:\n3  Nop\n");
+        let program = (vec![Instruction::Jump(2)], HashMap::new(), HashMap::new(), vec![]);
+        assert_eq!(disassemble(&program, &[], &[]), "1  Jump end\nend:\n");
+    }
+}
@@ -1,10 +1,9 @@
 
-use std::collections::{HashMap, VecDeque};
-use std::fs;
-use pest::iterators::{Pair, Pairs};
+use std::collections::VecDeque;
+use std::fmt;
+use pest::iterators::Pair;
 
 use crate::parser::Rule;
-use crate::virtual_machine::{BoolValue, FloatValue, Instruction, IntValue, StringValue, ValueType};
 
 #[derive(Debug, Clone)]
 pub enum EvalValue {
@@ -13,23 +12,42 @@ pub enum EvalValue {
     Stringliteral { val: String },
     FloatLiteral { val: String },
     BooleanLiteral { val: bool },
+    Wala,
     List { val: Vec<ExprAst> },
+    Map { val: Vec<(ExprAst, ExprAst)> },
 }
 
 #[derive(Debug, Clone)]
 pub enum ExprAst {
     Value {val: EvalValue },
     FunctionCall { name: String, args: Vec<ExprAst> },
+    MethodCall { receiver: Box<ExprAst>, name: String, args: Vec<ExprAst> },
+    Index { target: Box<ExprAst>, index: Box<ExprAst> },
+    /// a field read off any expression, like `xs[0].pangalan`, plain `a.b` stays a dotted variable name
+    MemberAccess { target: Box<ExprAst>, name: String },
     Addition { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
     Subtraction { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
     Division { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
     Multiplication { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    Modulo { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    FloorDivision { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    Power { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    Negate { expr: Box<ExprAst> },
+    BitAnd { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    BitOr { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    BitXor { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    ShiftLeft { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    ShiftRight { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    BitNot { expr: Box<ExprAst> },
     Eq { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
     Neq { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
     GtEq { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
     LtEq { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
     Gt { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
     Lt { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    And { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    Or { lhs: Box<ExprAst>, rhs: Box<ExprAst>},
+    Not { expr: Box<ExprAst> },
 }
 
 impl EvalValue {
@@ -60,25 +78,17 @@ impl EvalValue {
             _ => { unreachable!() }
         }
     }
-
-    pub fn get_reference(&self, hash: HashMap<String, EvalValue>) -> EvalValue {
-        match self {
-            Self::Reference { val } => {
-                hash.get(val).ok_or_else(|| { panic!("couldnt find {}", val) }).unwrap().clone()
-            }
-            _ => { unreachable!() }
-        }
-    }
 }
 
-impl ToString for EvalValue {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for EvalValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
             Self::Reference { val } => { val.clone() }
             Self::IntegerLiteral { val } => { val.clone() }
             Self::Stringliteral { val } => { val.clone() }
             Self::FloatLiteral { val } => { val.clone() }
             Self::BooleanLiteral { val } => { val.to_string() }
+            Self::Wala => { "wala".to_string() }
             EvalValue::List { val } => {
                 let mut s = "[".to_string();
                 for x in val {
@@ -89,7 +99,12 @@ impl ToString for EvalValue {
                 s.pop();
                 s
             }
-        }
+            EvalValue::Map { val } => {
+                let entries = val.iter().map(|(key, value)| format!("{}: {}", key, value)).collect::<Vec<String>>();
+                format!("{{{}}}", entries.join(", "))
+            }
+        };
+        f.write_str(&s)
     }
 }
 
@@ -98,15 +113,23 @@ impl ExprAst {
         match self {
             Self::Value { val } => { return StackItems::Value(val.clone()) }
             Self::FunctionCall { name, args } => {
-                let mut args = args.iter().map(|x| x.to_stack_item_value()).collect::<Vec<StackItems>>();
+                let args = args.iter().map(|x| x.to_stack_item_value()).collect::<Vec<StackItems>>();
                 return StackItems::FunctionCall(name.clone(), args);
             }
+            Self::MethodCall { .. } | Self::Index { .. } | Self::MemberAccess { .. } |
+            Self::And { .. } | Self::Or { .. } | Self::Not { .. } |
+            Self::Power { .. } | Self::Negate { .. } | Self::BitNot { .. } => { return StackItems::Node(self.clone()) }
             _ => {}
         }
 
         let op = match self {
             ExprAst::Value { .. } => { unreachable!()}
             ExprAst::FunctionCall { .. } => { unreachable!() }
+            ExprAst::MethodCall { .. } => { unreachable!() }
+            ExprAst::Index { .. } => { unreachable!() }
+            ExprAst::MemberAccess { .. } => { unreachable!() }
+            ExprAst::And { .. } | ExprAst::Or { .. } | ExprAst::Not { .. } => { unreachable!() }
+            ExprAst::Power { .. } | ExprAst::Negate { .. } | ExprAst::BitNot { .. } => { unreachable!() }
             ExprAst::Addition { .. } => { 0 }
             ExprAst::Subtraction { .. } => { 1 }
             ExprAst::Division { .. } => { 2 }
@@ -117,13 +140,33 @@ impl ExprAst {
             ExprAst::LtEq { .. } => { 7 }
             ExprAst::Gt { .. } => { 8 }
             ExprAst::Lt { .. } => { 9 }
+            ExprAst::Modulo { .. } => { 10 }
+            ExprAst::FloorDivision { .. } => { 11 }
+            ExprAst::BitAnd { .. } => { 12 }
+            ExprAst::BitOr { .. } => { 13 }
+            ExprAst::BitXor { .. } => { 14 }
+            ExprAst::ShiftLeft { .. } => { 15 }
+            ExprAst::ShiftRight { .. } => { 16 }
         };
 
         match self {
             Self::Division { lhs, rhs } |
             Self::Multiplication { lhs, rhs} |
+            Self::Modulo { lhs, rhs } |
+            Self::FloorDivision { lhs, rhs } |
+            Self::BitAnd { lhs, rhs } |
+            Self::BitOr { lhs, rhs } |
+            Self::BitXor { lhs, rhs } |
+            Self::ShiftLeft { lhs, rhs } |
+            Self::ShiftRight { lhs, rhs } |
             Self::Subtraction { lhs, rhs} |
-            Self::Addition { lhs, rhs}
+            Self::Addition { lhs, rhs} |
+            Self::Eq { lhs, rhs } |
+            Self::Neq { lhs, rhs } |
+            Self::GtEq { lhs, rhs } |
+            Self::LtEq { lhs, rhs } |
+            Self::Gt { lhs, rhs } |
+            Self::Lt { lhs, rhs }
             => {
                 StackItems::OperationWithValues(
                     op,
@@ -134,8 +177,11 @@ impl ExprAst {
             _ => unreachable!()
         }
     }
-    fn to_string(&self) -> String {
-        match self {
+}
+
+impl fmt::Display for ExprAst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
             Self::Value { val } => { val.to_string() }
             Self::FunctionCall { name, args } => {
                 let mut s = name.clone();
@@ -149,6 +195,25 @@ impl ExprAst {
                 s += ")";
                 s
             }
+            Self::MethodCall { receiver, name, args } => {
+                let mut s = receiver.to_string();
+                s += ".";
+                s += &*ExprAst::FunctionCall { name: name.clone(), args: args.clone() }.to_string();
+                s
+            }
+            Self::Index { target, index } => {
+                let mut s = target.to_string();
+                s += "[";
+                s += &*index.to_string();
+                s += "]";
+                s
+            }
+            Self::MemberAccess { target, name } => {
+                let mut s = target.to_string();
+                s += ".";
+                s += name;
+                s
+            }
             Self::Addition { lhs, rhs } => {
                 let mut s = lhs.to_string();
                 s += " + ";
@@ -173,6 +238,64 @@ impl ExprAst {
                 s += &*rhs.to_string();
                 s
             }
+            Self::Modulo { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " % ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::FloorDivision { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " // ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::Power { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " ** ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::Negate { expr } => {
+                let mut s = "-".to_string();
+                s += &*expr.to_string();
+                s
+            }
+            Self::BitAnd { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " & ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::BitOr { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " | ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::BitXor { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " ^ ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::ShiftLeft { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " << ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::ShiftRight { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " >> ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::BitNot { expr } => {
+                let mut s = "~".to_string();
+                s += &*expr.to_string();
+                s
+            }
             Self::Eq { lhs, rhs } => {
                 let mut s = lhs.to_string();
                 s += " == ";
@@ -209,7 +332,25 @@ impl ExprAst {
                 s += &*rhs.to_string();
                 s
             }
-        }
+            Self::And { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " ug ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::Or { lhs, rhs } => {
+                let mut s = lhs.to_string();
+                s += " o ";
+                s += &*rhs.to_string();
+                s
+            }
+            Self::Not { expr } => {
+                let mut s = "dili ".to_string();
+                s += &*expr.to_string();
+                s
+            }
+        };
+        f.write_str(&s)
     }
 }
 
@@ -217,9 +358,10 @@ impl ExprAst {
 #[derive(Debug)]
 enum StackItems{
     Value(EvalValue),
-    ValueBool(bool),
     Operation(u8),
     FunctionCall(String, Vec<StackItems>),
+    /// an expression that is already built and has nothing to fold, like a method call
+    Node(ExprAst),
     OperationWithValues(u8, Box<StackItems>, Box<StackItems>)
 }
 
@@ -233,6 +375,9 @@ impl StackItems {
                 let args = args.iter().map(|x| x.to_node()).collect::<Vec<ExprAst>>();
                 ExprAst::FunctionCall { name: name.clone(), args }
             }
+            Self::Node(node) => {
+                node.clone()
+            }
             Self::OperationWithValues( op, lhs, rhs) => {
                 let lhs = Box::new(lhs.to_node());
                 let rhs = Box::new(rhs.to_node());
@@ -248,6 +393,13 @@ impl StackItems {
                     7 => { ExprAst::LtEq {lhs, rhs}}
                     8 => { ExprAst::Gt {lhs, rhs}}
                     9 => { ExprAst::Lt {lhs, rhs}}
+                    10 => { ExprAst::Modulo {lhs, rhs}}
+                    11 => { ExprAst::FloorDivision {lhs, rhs}}
+                    12 => { ExprAst::BitAnd {lhs, rhs}}
+                    13 => { ExprAst::BitOr {lhs, rhs}}
+                    14 => { ExprAst::BitXor {lhs, rhs}}
+                    15 => { ExprAst::ShiftLeft {lhs, rhs}}
+                    16 => { ExprAst::ShiftRight {lhs, rhs}}
                     _ => unreachable!()
                 }
             }
@@ -268,31 +420,38 @@ fn str_to_op_no(op: &str) -> u8 {
         "<=" => { 7 }
         ">" => { 8 }
         "<" => { 9 }
+        "%" => { 10 }
+        "//" => { 11 }
+        "&" => { 12 }
+        "|" => { 13 }
+        "^" => { 14 }
+        "<<" => { 15 }
+        ">>" => { 16 }
         _ => { unreachable!() }
     }
 }
 
+fn negate_literal(literal: String) -> String {
+    match literal.strip_prefix('-') {
+        Some(positive) => positive.to_string(),
+        None => format!("-{}", literal),
+    }
+}
+
 fn parse_str(string: &str) -> String {
     let mut index = 0;
-    let str_len = string.len();
     let str_as_chars = string.chars().collect::<Vec<char>>();
+    let str_len = str_as_chars.len();
     let mut new_string = String::new();
     let chars_with_escape = vec![('n', '\n'), ('t', '\t'), ('r', '\r'), ('\\', '\\'), ('0', '\0'), ('"', '"' )];
     'outer: while  str_len > index {
         for char in &chars_with_escape {
-            if str_as_chars[index] == char.0 {
-                if index <= 2 {
-                    index += 1;
-                    new_string.push(char.0);
-                    continue 'outer
-                }
-
-                if str_as_chars[index - 1] == '\\' && str_as_chars[index - 2] != '\\' {
-                    index += 1;
-                    new_string.pop();
-                    new_string.push(char.1);
-                    continue 'outer
-                }
+            let escaped = index >= 1 && str_as_chars[index - 1] == '\\' && (index < 2 || str_as_chars[index - 2] != '\\');
+            if str_as_chars[index] == char.0 && escaped {
+                index += 1;
+                new_string.pop();
+                new_string.push(char.1);
+                continue 'outer
             }
         }
         new_string.push(str_as_chars[index]);
@@ -315,7 +474,7 @@ fn vec_deque_stack_items_to_number(mut items: VecDeque<StackItems>) -> ExprAst {
             Box::new(left.to_stack_item_value()),
             Box::new(right)
         ).to_node();
-        if items.len() == 0 { break }
+        if items.is_empty() { break }
     }
     left
 }
@@ -325,8 +484,26 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
     match rule.as_rule() {
         Rule::expr => {
             let mut pairs = rule.into_inner().collect::<VecDeque<Pair<Rule>>>();
-            let res = rule_expr_to_eval_expr(pairs.pop_back().unwrap());
-            res
+            rule_expr_to_eval_expr(pairs.pop_back().unwrap())
+        }
+        Rule::or_expr | Rule::and_expr => {
+            let is_or = rule.as_rule() == Rule::or_expr;
+            let mut pairs = rule.into_inner().filter(|pair| !matches!(pair.as_rule(), Rule::or_ops | Rule::and_ops));
+            let mut expr = rule_expr_to_eval_expr(pairs.next().unwrap());
+            for pair in pairs {
+                let lhs = Box::new(expr);
+                let rhs = Box::new(rule_expr_to_eval_expr(pair));
+                expr = if is_or { ExprAst::Or { lhs, rhs } } else { ExprAst::And { lhs, rhs } };
+            }
+            expr
+        }
+        Rule::not_expr => {
+            let mut pairs = rule.into_inner().collect::<Vec<Pair<Rule>>>();
+            let mut expr = rule_expr_to_eval_expr(pairs.pop().unwrap());
+            for _ in pairs {
+                expr = ExprAst::Not { expr: Box::new(expr) };
+            }
+            expr
         }
         Rule::bare_expr => {
             let mut pairs = rule.into_inner().collect::<VecDeque<Pair<Rule>>>();
@@ -350,7 +527,7 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
             }
             vec_deque_stack_items_to_number(items)
         }
-        Rule::sum => {
+        Rule::sum | Rule::bit_or | Rule::bit_xor | Rule::bit_and | Rule::shift => {
             let mut pairs = rule.into_inner().collect::<VecDeque<Pair<Rule>>>();
             if pairs.len() == 1 {
                 return rule_expr_to_eval_expr(pairs.pop_back().unwrap())
@@ -359,7 +536,7 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
             let mut items = VecDeque::new();
             for pair in pairs {
                 match pair.as_rule() {
-                    Rule::sum_ops => {
+                    Rule::sum_ops | Rule::bit_or_op | Rule::bit_xor_op | Rule::bit_and_op | Rule::shift_ops => {
                         items.push_back(
                             StackItems::Operation(str_to_op_no(pair.as_span().as_str()))
                         )
@@ -397,8 +574,59 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
             }
             vec_deque_stack_items_to_number(items)
         }
+        Rule::factor => {
+            let mut pairs = rule.into_inner().collect::<Vec<Pair<Rule>>>();
+            let mut expr = rule_expr_to_eval_expr(pairs.pop().unwrap());
+            // the operator closest to the operand applies first
+            for op in pairs.iter().rev() {
+                if op.as_str() == "~" {
+                    expr = ExprAst::BitNot { expr: Box::new(expr) };
+                    continue
+                }
+                expr = match expr {
+                    // keep negative literals as literals so -128 still fits in an Int8
+                    ExprAst::Value { val: EvalValue::IntegerLiteral { val } } => ExprAst::Value {
+                        val: EvalValue::IntegerLiteral { val: negate_literal(val) }
+                    },
+                    ExprAst::Value { val: EvalValue::FloatLiteral { val } } => ExprAst::Value {
+                        val: EvalValue::FloatLiteral { val: negate_literal(val) }
+                    },
+                    expr => ExprAst::Negate { expr: Box::new(expr) },
+                };
+            }
+            expr
+        }
+        Rule::power => {
+            let mut pairs = rule.into_inner().filter(|pair| pair.as_rule() != Rule::pow_op);
+            let lhs = rule_expr_to_eval_expr(pairs.next().unwrap());
+            match pairs.next() {
+                Some(rhs) => ExprAst::Power { lhs: Box::new(lhs), rhs: Box::new(rule_expr_to_eval_expr(rhs)) },
+                None => lhs,
+            }
+        }
         Rule::term => {
-            rule_expr_to_eval_expr(rule.into_inner().collect::<Vec<Pair<Rule>>>().pop().unwrap())
+            let mut pairs = rule.into_inner();
+            let mut expr = rule_expr_to_eval_expr(pairs.next().unwrap());
+            for suffix in pairs {
+                match suffix.as_rule() {
+                    Rule::member_call => {
+                        let mut call = suffix.into_inner().next().unwrap().into_inner();
+                        let name = call.next().unwrap().as_span().as_str().to_string();
+                        let args = call.map(rule_expr_to_eval_expr).collect();
+                        expr = ExprAst::MethodCall { receiver: Box::new(expr), name, args };
+                    }
+                    Rule::index => {
+                        let index = rule_expr_to_eval_expr(suffix.into_inner().next().unwrap());
+                        expr = ExprAst::Index { target: Box::new(expr), index: Box::new(index) };
+                    }
+                    Rule::member_access => {
+                        let name = suffix.into_inner().next().unwrap().as_str().to_string();
+                        expr = ExprAst::MemberAccess { target: Box::new(expr), name };
+                    }
+                    rule => unreachable!("Grammar error: {:?} after a term", rule)
+                }
+            }
+            expr
         }
         Rule::integer => {
             ExprAst::Value {
@@ -407,6 +635,24 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
                 }
             }
         }
+        Rule::fstring => {
+            // f"Ang {pangalan:>5}" is format("Ang {:>5}", pangalan), braces that are text stay doubled for format
+            let mut template = String::new();
+            let mut args = vec![];
+            for part in rule.into_inner() {
+                match part.as_rule() {
+                    Rule::fstring_text => template += &parse_str(part.as_str()),
+                    Rule::fstring_field => {
+                        let mut field = part.into_inner();
+                        args.push(rule_expr_to_eval_expr(field.next().unwrap()));
+                        template += &format!("{{{}}}", field.next().map_or("", |spec| spec.as_str()));
+                    }
+                    _ => unreachable!("Grammar error: {:?} in an f-string", part.as_rule())
+                }
+            }
+            args.insert(0, ExprAst::Value { val: EvalValue::Stringliteral { val: template } });
+            ExprAst::FunctionCall { name: "format".to_string(), args }
+        }
         Rule::string => {
             ExprAst::Value {
                 val: EvalValue::Stringliteral {
@@ -431,6 +677,9 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
                 }
             }
         }
+        Rule::wala => {
+            ExprAst::Value { val: EvalValue::Wala }
+        }
         Rule::identifier => {
             ExprAst::Value {
                 val: EvalValue::Reference {
@@ -447,14 +696,10 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
             }
         }
         Rule::list => {
-            let mut pairs = rule.into_inner().collect::<VecDeque<Pair<Rule>>>();
+            let pairs = rule.into_inner().collect::<VecDeque<Pair<Rule>>>();
             let mut items = vec![];
             for pair in pairs {
-                match pair.as_rule() {
-                    _ => {
-                        items.push(rule_expr_to_eval_expr(pair))
-                    }
-                }
+                items.push(rule_expr_to_eval_expr(pair))
             }
             ExprAst::Value {
                 val: EvalValue::List {
@@ -462,6 +707,20 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
                 }
             }
         }
+        Rule::map => {
+            let mut entries = vec![];
+            for entry in rule.into_inner() {
+                let mut pair = entry.into_inner();
+                let key = rule_expr_to_eval_expr(pair.next().unwrap());
+                let value = rule_expr_to_eval_expr(pair.next().unwrap());
+                entries.push((key, value))
+            }
+            ExprAst::Value {
+                val: EvalValue::Map {
+                    val: entries
+                }
+            }
+        }
         _ => unreachable!("{:?}", rule)
     }
 }
@@ -469,19 +728,3 @@ fn rule_expr_to_eval_expr(rule: Pair<Rule>) -> ExprAst
 pub fn parse_expression(rule: Pair<Rule>) -> ExprAst {
     rule_expr_to_eval_expr(rule)
 }
-
-pub fn parse_expressions(rules: Pairs<Rule>) -> Vec<ExprAst>{
-    let mut nums = vec![];
-    for rule in rules {
-        match rule.as_rule() {
-            Rule::EOI => { break }
-            Rule::expr => {
-                nums.push(
-                    rule_expr_to_eval_expr(rule)
-                )
-            }
-            _ => unreachable!("{:?}", rule)
-        }
-    }
-    nums
-}
\ No newline at end of file
@@ -0,0 +1,337 @@
+use num_bigint::BigInt;
+
+/// the biggest width or precision a spec can ask for, so {:99999999} can't make a huge string
+pub const MAX_WIDTH: usize = 10_000;
+
+/// what `format` needs to know about an argument, so the virtual machine and the node runner
+/// format their own values the same way
+pub trait FormatArgument: Sized {
+    /// how the value prints with a plain {}
+    fn display(&self) -> String;
+    /// Some for ints and floats, anything else can't take a precision
+    fn as_float(&self) -> Option<f64>;
+    /// Some for ints only, for the x, X, o and b specs
+    fn as_integer(&self) -> Option<BigInt>;
+    /// what a {name} placeholder finds in this argument, when it is a map or an object
+    fn named(&self, name: &str) -> Option<Self>;
+}
+
+/// which argument a placeholder takes
+#[derive(Debug, Clone, PartialEq)]
+enum Argument {
+    /// {}, the one after the previous {}
+    Next,
+    /// {0}, counted from the first argument after the format string
+    Index(usize),
+    /// {pangalan}, looked up in a map or object argument
+    Name(String),
+}
+
+/// everything after the colon in {0:>8.2}
+#[derive(Debug, Clone, PartialEq)]
+struct Spec {
+    fill: char,
+    align: Option<char>,
+    /// {:05} pads numbers with zeros after the sign
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    /// x, X, o, b or f
+    kind: Option<char>,
+}
+
+/// what is wrong with a format string or its arguments, the messages are in diagnostics
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    UnclosedBrace,
+    UnopenedBrace,
+    InvalidPlaceholder(String),
+    ArgumentTooBig(String),
+    MissingPrecision(String),
+    InvalidSpec(String),
+    /// a width or precision over MAX_WIDTH, including ones too long to even be a number
+    WidthTooBig(String),
+    /// x, X, o or b with a precision, ints have no digits after the point to cut
+    PrecisionOnInt(String),
+    /// x, X, o or b on something that is not an int, with how that value prints
+    NotAnInt { kind: char, value: String },
+    NotANumber(String),
+    UnknownName(String),
+    MixedPlaceholders,
+    MissingArgument { needed: usize, given: usize },
+    UnusedArguments { given: usize, used: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Placeholder(Argument, Spec),
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, FormatError> {
+    let mut chars: Vec<char> = spec.chars().collect();
+    let mut result = Spec { fill: ' ', align: None, zero: false, width: 0, precision: None, kind: None };
+    let is_align = |c: &char| matches!(c, '<' | '>' | '^');
+    if chars.len() >= 2 && is_align(&chars[1]) {
+        result.fill = chars.remove(0);
+        result.align = Some(chars.remove(0));
+    } else if chars.first().is_some_and(is_align) {
+        result.align = Some(chars.remove(0));
+    }
+    if chars.first() == Some(&'0') {
+        result.zero = true;
+        chars.remove(0);
+    }
+    let digits = |chars: &mut Vec<char>| {
+        let count = chars.iter().take_while(|c| c.is_ascii_digit()).count();
+        if count == 0 {
+            return Ok(None)
+        }
+        let number: String = chars.drain(..count).collect();
+        match number.parse::<usize>() {
+            Ok(number) if number <= MAX_WIDTH => Ok(Some(number)),
+            _ => Err(FormatError::WidthTooBig(spec.to_string())),
+        }
+    };
+    result.width = digits(&mut chars)?.unwrap_or(0);
+    if chars.first() == Some(&'.') {
+        chars.remove(0);
+        result.precision = Some(digits(&mut chars)?.ok_or_else(|| FormatError::MissingPrecision(spec.to_string()))?);
+    }
+    match chars.as_slice() {
+        [] => {}
+        ['x' | 'X' | 'o' | 'b'] if result.precision.is_some() => {
+            return Err(FormatError::PrecisionOnInt(spec.to_string()))
+        }
+        [kind @ ('x' | 'X' | 'o' | 'b' | 'f')] => result.kind = Some(*kind),
+        _ => return Err(FormatError::InvalidSpec(spec.to_string())),
+    }
+    Ok(result)
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Piece, FormatError> {
+    let (argument, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+    let argument = match argument.trim() {
+        "" => Argument::Next,
+        index if index.chars().all(|c| c.is_ascii_digit()) => Argument::Index(index.parse().map_err(|_| FormatError::ArgumentTooBig(index.to_string()))?),
+        name if name.chars().all(|c| c.is_alphanumeric() || c == '_') => Argument::Name(name.to_string()),
+        other => return Err(FormatError::InvalidPlaceholder(other.to_string())),
+    };
+    Ok(Piece::Placeholder(argument, parse_spec(spec)?))
+}
+
+/// splits "a {0:>3} b {{c}}" into text and placeholders, {{ and }} are a literal brace
+fn parse_template(template: &str) -> Result<Vec<Piece>, FormatError> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); text.push('{') }
+            '}' if chars.peek() == Some(&'}') => { chars.next(); text.push('}') }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') | None => return Err(FormatError::UnclosedBrace),
+                        Some(c) => placeholder.push(c),
+                    }
+                }
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(parse_placeholder(&placeholder)?);
+            }
+            '}' => return Err(FormatError::UnopenedBrace),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+fn render<T: FormatArgument>(value: &T, spec: &Spec) -> Result<String, FormatError> {
+    let is_number = value.as_float().is_some();
+    let text = match spec.kind {
+        Some(kind @ ('x' | 'X' | 'o' | 'b')) => {
+            let int = value.as_integer().ok_or_else(|| FormatError::NotAnInt { kind, value: value.display() })?;
+            match kind {
+                'x' => format!("{:x}", int),
+                'X' => format!("{:X}", int),
+                'o' => format!("{:o}", int),
+                _ => format!("{:b}", int),
+            }
+        }
+        Some('f') => {
+            let float = value.as_float().ok_or_else(|| FormatError::NotANumber(value.display()))?;
+            format!("{:.*}", spec.precision.unwrap_or(6), float)
+        }
+        _ => match (spec.precision, value.as_float()) {
+            (Some(precision), Some(float)) => format!("{:.*}", precision, float),
+            // a precision on anything else cuts it to that many characters
+            (Some(precision), None) => value.display().chars().take(precision).collect(),
+            (None, _) => value.display(),
+        }
+    };
+
+    let length = text.chars().count();
+    if length >= spec.width {
+        return Ok(text)
+    }
+    let padding = spec.width - length;
+    if spec.zero && spec.align.is_none() && is_number {
+        let (sign, digits) = text.split_at(if text.starts_with('-') { 1 } else { 0 });
+        return Ok(format!("{}{}{}", sign, "0".repeat(padding), digits))
+    }
+    let fill = |count: usize| spec.fill.to_string().repeat(count);
+    // numbers line up on the right and everything else on the left, unless the spec says otherwise
+    Ok(match spec.align.unwrap_or(if is_number { '>' } else { '<' }) {
+        '<' => format!("{}{}", text, fill(padding)),
+        '>' => format!("{}{}", fill(padding), text),
+        _ => format!("{}{}{}", fill(padding / 2), text, fill(padding - padding / 2)),
+    })
+}
+
+/// fills the placeholders in `template` with `args`, every argument has to be used and every placeholder
+/// has to have one, so a typo in the format string is an error instead of a silently wrong output
+pub fn format<T: FormatArgument>(template: &str, args: &[T]) -> Result<String, FormatError> {
+    let pieces = parse_template(template)?;
+    let mut used = vec![false; args.len()];
+    let mut next = 0;
+    let (mut automatic, mut manual) = (false, false);
+    let mut output = String::new();
+    for piece in pieces {
+        let (argument, spec) = match piece {
+            Piece::Text(text) => { output += &text; continue }
+            Piece::Placeholder(argument, spec) => (argument, spec),
+        };
+        let rendered = match argument {
+            Argument::Name(name) => {
+                let found = args.iter().enumerate().find_map(|(index, arg)| arg.named(&name).map(|value| (index, value)));
+                let Some((index, value)) = found else {
+                    return Err(FormatError::UnknownName(name))
+                };
+                used[index] = true;
+                render(&value, &spec)?
+            }
+            Argument::Next | Argument::Index(_) => {
+                let index = match argument {
+                    Argument::Index(index) => { manual = true; index }
+                    _ => { automatic = true; next += 1; next - 1 }
+                };
+                if automatic && manual {
+                    return Err(FormatError::MixedPlaceholders)
+                }
+                let Some(value) = args.get(index) else {
+                    return Err(FormatError::MissingArgument { needed: index + 1, given: args.len() })
+                };
+                used[index] = true;
+                render(value, &spec)?
+            }
+        };
+        output += &rendered;
+    }
+    let unused = used.iter().filter(|used| !**used).count();
+    if unused > 0 {
+        return Err(FormatError::UnusedArguments { given: args.len(), used: args.len() - unused })
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    enum Arg {
+        Int(i64),
+        Float(f64),
+        Text(&'static str),
+        Named(&'static str, i64),
+    }
+    use Arg::*;
+
+    impl FormatArgument for Arg {
+        fn display(&self) -> String {
+            match self {
+                Int(int) => int.to_string(),
+                Float(float) => float.to_string(),
+                Text(text) => text.to_string(),
+                Named(name, value) => format!("{{{}: {}}}", name, value),
+            }
+        }
+        fn as_float(&self) -> Option<f64> {
+            match self {
+                Int(int) => Some(*int as f64),
+                Float(float) => Some(*float),
+                _ => None,
+            }
+        }
+        fn as_integer(&self) -> Option<BigInt> {
+            match self {
+                Int(int) => Some(BigInt::from(*int)),
+                _ => None,
+            }
+        }
+        fn named(&self, name: &str) -> Option<Self> {
+            match self {
+                Named(key, value) if *key == name => Some(Int(*value)),
+                _ => None,
+            }
+        }
+    }
+
+    fn ok(template: &str, args: &[Arg]) -> String {
+        format(template, args).unwrap()
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(ok("{} ug {}", &[Text("Juan"), Int(3)]), "Juan ug 3");
+        assert_eq!(ok("{1} {0} {1}", &[Int(1), Int(2)]), "2 1 2");
+        assert_eq!(ok("{edad} anyos", &[Named("edad", 31)]), "31 anyos");
+        assert_eq!(ok("{{}} {{{}}}", &[Int(5)]), "{} {5}");
+    }
+
+    #[test]
+    fn width_fill_and_alignment() {
+        assert_eq!(ok("[{:5}]", &[Int(42)]), "[   42]", "numbers go right");
+        assert_eq!(ok("[{:5}]", &[Text("ab")]), "[ab   ]", "text goes left");
+        assert_eq!(ok("[{:^6}]", &[Text("ab")]), "[  ab  ]");
+        assert_eq!(ok("[{:*<4}]", &[Int(7)]), "[7***]");
+        assert_eq!(ok("[{:05}]", &[Int(-42)]), "[-0042]", "zeros go after the sign");
+        assert_eq!(ok("[{:2}]", &[Text("mas taas")]), "[mas taas]", "never cuts");
+        assert_eq!(ok("[{:>3}]", &[Text("ñ")]), "[  ñ]", "counts characters");
+    }
+
+    #[test]
+    fn precision_and_types() {
+        assert_eq!(ok("{:.2}", &[Float(1.23456)]), "1.23");
+        assert_eq!(ok("{:8.3}", &[Float(2.5)]), "   2.500");
+        assert_eq!(ok("{:.3}", &[Text("Mariano")]), "Mar", "a precision cuts text");
+        assert_eq!(ok("{:f}", &[Int(2)]), "2.000000");
+        assert_eq!(ok("{:x} {:X} {:o} {:b}", &[Int(255), Int(255), Int(8), Int(5)]), "ff FF 10 101");
+        assert_eq!(ok("{:08b}", &[Int(5)]), "00000101");
+    }
+
+    #[test]
+    fn errors() {
+        let error = |template: &str, args: &[Arg]| format(template, args).unwrap_err();
+        assert_eq!(error("{} {0}", &[Int(1)]), FormatError::MixedPlaceholders);
+        assert_eq!(error("{} {}", &[Int(1)]), FormatError::MissingArgument { needed: 2, given: 1 });
+        assert_eq!(error("{}", &[Int(1), Int(2)]), FormatError::UnusedArguments { given: 2, used: 1 });
+        assert_eq!(error("{ngalan}", &[Int(1)]), FormatError::UnknownName("ngalan".to_string()));
+        assert_eq!(error("{:x}", &[Float(1.5)]), FormatError::NotAnInt { kind: 'x', value: "1.5".to_string() });
+        assert_eq!(error("{:.}", &[Int(1)]), FormatError::MissingPrecision(".".to_string()));
+        assert_eq!(error("{:q}", &[Int(1)]), FormatError::InvalidSpec("q".to_string()));
+        assert_eq!(error("{:99999999999999999999}", &[Int(1)]), FormatError::WidthTooBig("99999999999999999999".to_string()));
+        assert_eq!(error("{:10001}", &[Int(1)]), FormatError::WidthTooBig("10001".to_string()));
+        assert_eq!(error("{:.10001}", &[Float(1.5)]), FormatError::WidthTooBig(".10001".to_string()));
+        assert_eq!(error("{:.3x}", &[Int(1)]), FormatError::PrecisionOnInt(".3x".to_string()));
+        assert_eq!(error("{", &[]), FormatError::UnclosedBrace);
+        assert_eq!(error("}", &[]), FormatError::UnopenedBrace);
+    }
+}
@@ -0,0 +1,199 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::{Rc, Weak};
+use crate::virtual_machine::{MapValue, Object, Value, ValueType};
+
+/// how many lists and objects can be tracked before the first collection
+const INITIAL_THRESHOLD: usize = 1024;
+
+/// a list, map or object the vm allocated, held weakly so tracking it doesn't keep it alive
+#[derive(Debug)]
+enum HeapCell {
+    List(Weak<RefCell<Vec<Value>>>),
+    Map(Weak<RefCell<MapValue>>),
+    Object(Weak<RefCell<Object>>),
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GcStats {
+    pub collections: usize,
+    /// cells freed by the collector over the whole run, not counting the ones reference counting freed on its own
+    pub freed: usize,
+    /// cells that are still alive right now
+    pub live: usize,
+    pub threshold: usize,
+}
+
+/// Lists and objects are reference counted, so most of them are freed as soon as the last handle goes away.
+/// Cycles never get there, so the heap remembers everything the vm allocates, and a collection marks
+/// whatever the roots can reach and empties the rest, which breaks the cycles and lets them drop.
+#[derive(Debug)]
+pub struct Heap {
+    cells: Vec<HeapCell>,
+    threshold: usize,
+    stats: GcStats,
+    pub trace: bool,
+}
+
+fn list_id(list: &Rc<RefCell<Vec<Value>>>) -> usize {
+    Rc::as_ptr(list) as *const () as usize
+}
+
+fn map_id(map: &Rc<RefCell<MapValue>>) -> usize {
+    Rc::as_ptr(map) as *const () as usize
+}
+
+fn object_id(object: &Rc<RefCell<Object>>) -> usize {
+    Rc::as_ptr(object) as *const () as usize
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self { cells: vec![], threshold: INITIAL_THRESHOLD, stats: GcStats::default(), trace: false }
+    }
+
+    /// call this on every new list, map or object, anything else is ignored
+    pub fn track(&mut self, value: &Value) {
+        match value {
+            Value::Value(ValueType::Vector(list)) => self.cells.push(HeapCell::List(Rc::downgrade(list))),
+            Value::Value(ValueType::Map(map)) => self.cells.push(HeapCell::Map(Rc::downgrade(map))),
+            Value::Object(object) => self.cells.push(HeapCell::Object(Rc::downgrade(object))),
+            Value::Value(_) => {}
+        }
+    }
+
+    pub fn should_collect(&self) -> bool {
+        self.cells.len() >= self.threshold
+    }
+
+    pub fn stats(&self) -> GcStats {
+        let live = self.cells.iter().filter(|cell| match cell {
+            HeapCell::List(list) => list.strong_count() > 0,
+            HeapCell::Map(map) => map.strong_count() > 0,
+            HeapCell::Object(object) => object.strong_count() > 0,
+        }).count();
+        GcStats { live, threshold: self.threshold, ..self.stats }
+    }
+
+    fn mark<'a>(roots: impl Iterator<Item = &'a Value>) -> HashSet<usize> {
+        let mut marked = HashSet::new();
+        let mut pending = roots.cloned().collect::<Vec<Value>>();
+        while let Some(value) = pending.pop() {
+            match value {
+                Value::Value(ValueType::Vector(list)) => {
+                    if marked.insert(list_id(&list)) {
+                        pending.extend(list.borrow().iter().cloned());
+                    }
+                }
+                Value::Value(ValueType::Map(map)) => {
+                    if marked.insert(map_id(&map)) {
+                        pending.extend(map.borrow().values().cloned());
+                    }
+                }
+                Value::Object(object) => {
+                    if marked.insert(object_id(&object)) {
+                        pending.extend(object.borrow().members().cloned());
+                    }
+                }
+                Value::Value(_) => {}
+            }
+        }
+        marked
+    }
+
+    /// returns how many unreachable cells were freed
+    pub fn collect<'a>(&mut self, roots: impl Iterator<Item = &'a Value>) -> usize {
+        let marked = Self::mark(roots);
+        let tracked = self.cells.len();
+        let mut garbage = vec![];
+        self.cells.retain(|cell| match cell {
+            HeapCell::List(list) => match list.upgrade() {
+                Some(list) if !marked.contains(&list_id(&list)) => { garbage.push(Value::Value(ValueType::Vector(list))); false }
+                Some(_) => true,
+                None => false,
+            },
+            HeapCell::Map(map) => match map.upgrade() {
+                Some(map) if !marked.contains(&map_id(&map)) => { garbage.push(Value::Value(ValueType::Map(map))); false }
+                Some(_) => true,
+                None => false,
+            },
+            HeapCell::Object(object) => match object.upgrade() {
+                Some(object) if !marked.contains(&object_id(&object)) => { garbage.push(Value::Object(object)); false }
+                Some(_) => true,
+                None => false,
+            },
+        });
+
+        // nothing reachable points at the garbage, so emptying it can't be observed by the program
+        let freed = garbage.len();
+        for value in &garbage {
+            match value {
+                Value::Value(ValueType::Vector(list)) => list.borrow_mut().clear(),
+                Value::Value(ValueType::Map(map)) => map.borrow_mut().clear(),
+                Value::Object(object) => object.borrow_mut().clear_members(),
+                Value::Value(_) => {}
+            }
+        }
+        drop(garbage);
+
+        self.stats.collections += 1;
+        self.stats.freed += freed;
+        self.threshold = INITIAL_THRESHOLD.max(self.cells.len() * 2);
+        if self.trace {
+            eprintln!(
+                "gc #{}: {} tracked, {} freed, {} live, next collection at {}",
+                self.stats.collections, tracked, freed, self.cells.len(), self.threshold
+            );
+        }
+        freed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a list that holds itself, so reference counting alone never frees it
+    fn cycle(heap: &mut Heap) -> (Value, Weak<RefCell<Vec<Value>>>) {
+        let list = Value::Value(ValueType::new_list(vec![]));
+        let Value::Value(ValueType::Vector(cell)) = &list else { unreachable!() };
+        cell.borrow_mut().push(list.clone());
+        heap.track(&list);
+        let weak = Rc::downgrade(cell);
+        (list, weak)
+    }
+
+    #[test]
+    fn frees_unreachable_cycles() {
+        let mut heap = Heap::new();
+        let (list, weak) = cycle(&mut heap);
+        drop(list);
+        assert!(weak.upgrade().is_some(), "the cycle keeps itself alive");
+        assert_eq!(heap.collect(std::iter::empty()), 1);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(heap.stats().freed, 1);
+        assert_eq!(heap.stats().live, 0);
+    }
+
+    #[test]
+    fn keeps_what_the_roots_reach() {
+        let mut heap = Heap::new();
+        let (inner, weak) = cycle(&mut heap);
+        let root = Value::Value(ValueType::new_list(vec![inner]));
+        heap.track(&root);
+        assert_eq!(heap.collect([&root].into_iter()), 0);
+        assert!(weak.upgrade().is_some());
+        assert_eq!(heap.stats().live, 2);
+    }
+
+    #[test]
+    fn waits_for_the_threshold() {
+        let mut heap = Heap::new();
+        for _ in 0..INITIAL_THRESHOLD - 1 {
+            heap.track(&Value::Value(ValueType::new_list(vec![])));
+        }
+        assert!(!heap.should_collect());
+        heap.track(&Value::Value(ValueType::new_list(vec![])));
+        assert!(heap.should_collect());
+    }
+}
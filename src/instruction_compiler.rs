@@ -1,207 +1,404 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::diagnostics::{Diagnostic, ErrorKind};
 use crate::eval_parser::{EvalValue, ExprAst};
-use crate::parser::{Block, Statement};
-use crate::virtual_machine::{BoolValue, FloatValue, Instruction, IntValue, StringValue, Object, ObjectCreator, Value, ValueType};
+use crate::parser::{parse_file_data, Block, Span, Statement};
+use crate::verifier::{call_effect, stack_effect};
+use num_bigint::BigInt;
+use crate::virtual_machine::{BoolValue, FloatValue, Instruction, IntValue, StringValue, ObjectCreator, ValueType, NATIVE_MODULES};
+
+/// instructions, function entry points, class constructors and the span of each instruction
+pub type CompiledProgram = (Vec<Instruction>, HashMap<String, usize>, HashMap<String, ObjectCreator>, Vec<Span>);
 
 #[derive(Debug, Clone)]
 enum Translation {
     Instruction(Instruction),
     Label(usize),
     Jump(u8, usize),
+    /// every instruction after this one came from the statement at this span
+    Position(Span),
+}
+
+/// how many values a statement leaves on the stack when it finishes normally, jumps are followed so both
+/// sides of an `ug` count once, and code only reached by a Call like a proseso body is skipped
+fn values_left(translations: &[Translation]) -> usize {
+    let mut at_labels: HashMap<usize, isize> = HashMap::new();
+    let mut depth = Some(0);
+    let mut previous = None;
+    for translation in translations {
+        match (translation, depth) {
+            (Translation::Label(label), None) => depth = at_labels.get(label).copied(),
+            (Translation::Jump(jump, label), Some(current)) => {
+                // 1 and 2 are the conditional jumps that pop their condition, 3 is a TryBegin whose handler gets the error
+                let current = if matches!(jump, 1 | 2) { current - 1 } else { current };
+                at_labels.entry(*label).or_insert(if *jump == 3 { current + 1 } else { current });
+                depth = if *jump == 0 { None } else { Some(current) };
+            }
+            (Translation::Instruction(instruction), Some(current)) => {
+                let effect = match instruction {
+                    Instruction::Return | Instruction::Throw => None,
+                    Instruction::Call(_) | Instruction::CallMethod(_) => Some(call_effect(instruction, previous).expect("a call without its argument count")),
+                    instruction => Some(stack_effect(instruction)),
+                };
+                depth = effect.map(|(pops, pushes)| current - pops as isize + pushes as isize);
+            }
+            _ => {}
+        }
+        if let Translation::Instruction(instruction) = translation {
+            previous = Some(instruction);
+        }
+    }
+    depth.unwrap_or(0).max(0) as usize
 }
 
 pub struct InstructionCompiler {
     instructions: Vec<Instruction>,
-    function_translations: Vec<Translation>,
+    source_map: Vec<Span>,
     functions_label_locations: Vec<(String, usize)>,
+    /// class name, method name and the label of the method's entry
+    methods_label_locations: Vec<(String, String, usize)>,
     functions_locations: HashMap<String, usize>,
     labels: HashMap<usize, usize>,
     label_count: usize,
     class_creators: HashMap<String, ObjectCreator>,
-    class_details: HashMap<String, (usize, Vec<String>)>
+    class_details: HashMap<String, (usize, Vec<String>)>,
+    /// variables declared by the blocks we are currently inside of, innermost last
+    live_variables: Vec<String>,
+    /// how many live variables and try scopes there were when each enclosing loop body started,
+    /// buwag and tiwas leave every sulayi and delete everything declared after that before jumping
+    loop_scopes: Vec<(usize, usize)>,
+    /// the sulayi handlers we are currently inside of, innermost last, with the katapusan block to run when leaving one early
+    try_scopes: Vec<Option<Block>>,
+    /// every source file compiled so far, the file that was run first, Span::file indexes into this
+    files: Vec<PathBuf>,
+    /// the file whose statements are being compiled right now
+    current_file: u32,
+    /// modules that were already compiled and the namespace their proseso and klase live under
+    modules: HashMap<PathBuf, String>,
+    /// the chain of files being imported right now, to catch a module that imports itself again
+    importing: Vec<(PathBuf, String)>,
+    /// namespace of the module being compiled, None for the file that was run
+    namespace: Option<String>,
+    /// the proseso and klase names the module being compiled defines, calls to these get the namespace
+    module_names: HashSet<String>,
+    /// module names as written in the current file's ipasulod, to the namespace they were compiled under
+    imports: HashMap<String, String>,
 }
 
 impl InstructionCompiler {
     pub fn new() -> Self {
         Self {
             instructions: vec![],
-            function_translations: vec![],
+            source_map: vec![],
             functions_label_locations: vec![],
+            methods_label_locations: vec![],
             functions_locations: HashMap::new(),
             labels: HashMap::new(),
             label_count: 0,
             class_creators: HashMap::new(),
-            class_details: HashMap::new()
+            class_details: HashMap::new(),
+            live_variables: vec![],
+            loop_scopes: vec![],
+            try_scopes: vec![],
+            files: vec![],
+            current_file: 0,
+            modules: HashMap::new(),
+            importing: vec![],
+            namespace: None,
+            module_names: HashSet::new(),
+            imports: HashMap::new(),
         }
     }
 
-    fn compile_expr_ast(&self, expr: ExprAst) -> Vec<Instruction> {
+    fn compile_expr_ast(&mut self, expr: ExprAst) -> Vec<Translation> {
         let mut inst = vec![];
         match expr {
             ExprAst::Value { val } => {
                 match val {
                     EvalValue::IntegerLiteral { val } => {
                         if val.parse::<i8>().is_ok() {
-                            inst.push(Instruction::Push(ValueType::Int(IntValue::Int8(val.parse::<i8>().unwrap()))))
+                            inst.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int8(val.parse::<i8>().unwrap())))))
                         } else if val.parse::<i16>().is_ok() {
-                            inst.push(Instruction::Push(ValueType::Int(IntValue::Int16(val.parse::<i16>().unwrap()))))
+                            inst.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int16(val.parse::<i16>().unwrap())))))
                         } else if val.parse::<i32>().is_ok() {
-                            inst.push(Instruction::Push(ValueType::Int(IntValue::Int32(val.parse::<i32>().unwrap()))))
+                            inst.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(val.parse::<i32>().unwrap())))))
                         } else if val.parse::<i64>().is_ok() {
-                            inst.push(Instruction::Push(ValueType::Int(IntValue::Int64(val.parse::<i64>().unwrap()))))
+                            inst.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int64(val.parse::<i64>().unwrap())))))
                         } else {
-                            unreachable!("too big of an int, I suggest using floats instead")
+                            inst.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::BigInt(val.parse::<BigInt>().unwrap())))))
                         }
                     }
                     EvalValue::FloatLiteral { val } => {
                         if val.parse::<f32>().is_ok() {
-                            inst.push(Instruction::Push(ValueType::Float(FloatValue::Float32(val.parse::<f32>().unwrap()))))
+                            inst.push(Translation::Instruction(Instruction::Push(ValueType::Float(FloatValue::Float32(val.parse::<f32>().unwrap())))))
                         } else if val.parse::<f64>().is_ok() {
-                            inst.push(Instruction::Push(ValueType::Float(FloatValue::Float64(val.parse::<f64>().unwrap()))))
+                            inst.push(Translation::Instruction(Instruction::Push(ValueType::Float(FloatValue::Float64(val.parse::<f64>().unwrap())))))
                         } else {
                             unreachable!("too big of a float, unfortunately no fix yet, unless f128 is implemented")
                         }
                     }
                     EvalValue::Stringliteral { val} => {
-                        inst.push(Instruction::Push(ValueType::String(StringValue::new(val.clone()))))
+                        inst.push(Translation::Instruction(Instruction::Push(ValueType::String(StringValue::new(val.clone())))))
                     }
                     EvalValue::BooleanLiteral { val } => {
-                        inst.push(Instruction::Push(ValueType::Bool(BoolValue::new(val))))
+                        inst.push(Translation::Instruction(Instruction::Push(ValueType::Bool(BoolValue::new(val)))))
+                    }
+                    EvalValue::Wala => {
+                        inst.push(Translation::Instruction(Instruction::Push(ValueType::Wala)))
                     }
                     EvalValue::Reference { val } => {
-                        inst.push(Instruction::Load(val.clone()))
+                        inst.push(Translation::Instruction(Instruction::Load(val.clone())))
                     }
                     EvalValue::List { val } => {
-                        inst.push(Instruction::Push(ValueType::Vector(vec![])));
+                        // every evaluation builds a new list, lists are shared by reference after that
+                        let length = val.len();
                         for value in val {
                             inst.append(&mut self.compile_expr_ast(value));
-                            inst.push(Instruction::Push(ValueType::Int(IntValue::Int32(2))));
-                            inst.push(Instruction::Call("push".to_string()));
                         }
+                        inst.push(Translation::Instruction(Instruction::BuildList(length)));
+                    }
+                    EvalValue::Map { val } => {
+                        let length = val.len();
+                        for (key, value) in val {
+                            inst.append(&mut self.compile_expr_ast(key));
+                            inst.append(&mut self.compile_expr_ast(value));
+                        }
+                        inst.push(Translation::Instruction(Instruction::BuildMap(length)));
                     }
-                    _ => { unreachable!() }
                 }
             }
             ExprAst::FunctionCall { name, args } => {
+                inst.append(&mut self.compile_call(name, args))
+            }
+            ExprAst::MethodCall { receiver, name, args } => {
                 let args_len = args.len();
-                if self.class_details.contains_key(&*name){
-
-                }
+                inst.append(&mut self.compile_expr_ast(*receiver));
                 for arg in args {
                     inst.append(&mut self.compile_expr_ast(arg))
                 }
-                inst.push(Instruction::Push(ValueType::Int(IntValue::Int32(args_len as i32))));
-                inst.push(Instruction::Call(name.clone()))
+                inst.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(args_len as i32)))));
+                inst.push(Translation::Instruction(Instruction::CallMethod(name)))
+            }
+            ExprAst::Index { target, index } => {
+                inst.append(&mut self.compile_expr_ast(*target));
+                inst.append(&mut self.compile_expr_ast(*index));
+                inst.push(Translation::Instruction(Instruction::Index))
+            }
+            ExprAst::MemberAccess { target, name } => {
+                inst.append(&mut self.compile_expr_ast(*target));
+                inst.push(Translation::Instruction(Instruction::LoadMember(name)))
+            }
+            ExprAst::And { lhs, rhs } => {
+                // ug skips the right side once the left is false, either way the result is a bool
+                let false_label = self.label_count;
+                self.label_count += 1;
+                let end_label = self.label_count;
+                self.label_count += 1;
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.push(Translation::Jump(2, false_label));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Jump(2, false_label));
+                inst.push(Translation::Instruction(Instruction::Push(ValueType::Bool(BoolValue::new(true)))));
+                inst.push(Translation::Jump(0, end_label));
+                inst.push(Translation::Label(false_label));
+                inst.push(Translation::Instruction(Instruction::Push(ValueType::Bool(BoolValue::new(false)))));
+                inst.push(Translation::Label(end_label));
+            }
+            ExprAst::Or { lhs, rhs } => {
+                // o skips the right side once the left is true
+                let true_label = self.label_count;
+                self.label_count += 1;
+                let end_label = self.label_count;
+                self.label_count += 1;
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.push(Translation::Jump(1, true_label));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Jump(1, true_label));
+                inst.push(Translation::Instruction(Instruction::Push(ValueType::Bool(BoolValue::new(false)))));
+                inst.push(Translation::Jump(0, end_label));
+                inst.push(Translation::Label(true_label));
+                inst.push(Translation::Instruction(Instruction::Push(ValueType::Bool(BoolValue::new(true)))));
+                inst.push(Translation::Label(end_label));
+            }
+            ExprAst::Not { expr } => {
+                let false_label = self.label_count;
+                self.label_count += 1;
+                let end_label = self.label_count;
+                self.label_count += 1;
+                inst.append(&mut self.compile_expr_ast(*expr));
+                inst.push(Translation::Jump(2, false_label));
+                inst.push(Translation::Instruction(Instruction::Push(ValueType::Bool(BoolValue::new(false)))));
+                inst.push(Translation::Jump(0, end_label));
+                inst.push(Translation::Label(false_label));
+                inst.push(Translation::Instruction(Instruction::Push(ValueType::Bool(BoolValue::new(true)))));
+                inst.push(Translation::Label(end_label));
             }
             ExprAst::Addition { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::Add)
+                inst.push(Translation::Instruction(Instruction::Add))
             }
             ExprAst::Subtraction { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::Sub)
+                inst.push(Translation::Instruction(Instruction::Sub))
             }
             ExprAst::Division { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::Div)
+                inst.push(Translation::Instruction(Instruction::Div))
             }
             ExprAst::Multiplication { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::Mul)
+                inst.push(Translation::Instruction(Instruction::Mul))
+            }
+            ExprAst::Modulo { lhs, rhs } => {
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Instruction(Instruction::Mod))
+            }
+            ExprAst::FloorDivision { lhs, rhs } => {
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Instruction(Instruction::FloorDiv))
+            }
+            ExprAst::Power { lhs, rhs } => {
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Instruction(Instruction::Pow))
+            }
+            ExprAst::Negate { expr } => {
+                inst.append(&mut self.compile_expr_ast(*expr));
+                inst.push(Translation::Instruction(Instruction::Neg))
+            }
+            ExprAst::BitAnd { lhs, rhs } => {
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Instruction(Instruction::BitAnd))
+            }
+            ExprAst::BitOr { lhs, rhs } => {
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Instruction(Instruction::BitOr))
+            }
+            ExprAst::BitXor { lhs, rhs } => {
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Instruction(Instruction::BitXor))
+            }
+            ExprAst::ShiftLeft { lhs, rhs } => {
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Instruction(Instruction::Shl))
+            }
+            ExprAst::ShiftRight { lhs, rhs } => {
+                inst.append(&mut self.compile_expr_ast(*lhs));
+                inst.append(&mut self.compile_expr_ast(*rhs));
+                inst.push(Translation::Instruction(Instruction::Shr))
+            }
+            ExprAst::BitNot { expr } => {
+                inst.append(&mut self.compile_expr_ast(*expr));
+                inst.push(Translation::Instruction(Instruction::BitNot))
             }
             ExprAst::Eq { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::Eq)
+                inst.push(Translation::Instruction(Instruction::Eq))
             }
             ExprAst::Neq { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::Neq)
+                inst.push(Translation::Instruction(Instruction::Neq))
             }
             ExprAst::GtEq { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::GtEq)
+                inst.push(Translation::Instruction(Instruction::GtEq))
             }
             ExprAst::LtEq { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::LtEq)
+                inst.push(Translation::Instruction(Instruction::LtEq))
             }
             ExprAst::Gt { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::Gt)
+                inst.push(Translation::Instruction(Instruction::Gt))
             }
             ExprAst::Lt { lhs, rhs } => {
                 inst.append(&mut self.compile_expr_ast(*lhs));
                 inst.append(&mut self.compile_expr_ast(*rhs));
-                inst.push(Instruction::Lt)
+                inst.push(Translation::Instruction(Instruction::Lt))
             }
         }
         inst
     }
 
-    fn compile_block(&mut self, statements: Block, start_label: Option<usize>, end_label: Option<usize>, in_a_function: bool) -> Vec<Translation> {
+    fn compile_block(&mut self, statements: Block, start_label: Option<usize>, end_label: Option<usize>, in_a_function: bool) -> Result<Vec<Translation>, Diagnostic> {
         let mut translations = vec![];
-        let mut assignments = vec![];
-        for statement in statements {
+        let scope_start = self.live_variables.len();
+        for (span, statement) in statements {
+            let span = Span { file: self.current_file, ..span };
+            translations.push(Translation::Position(span));
+            let statement_start = translations.len();
             match statement {
                 Statement::VariableAssignment { name, value } => {
-                    assignments.push(name.clone());
-                    translations.append(&mut self.compile_expr_ast(value).iter().map(|x| Translation::Instruction(x.clone())).collect());
+                    self.live_variables.push(name.clone());
+                    translations.append(&mut self.compile_expr_ast(value));
                     translations.push(Translation::Instruction(Instruction::NewVariable(name.clone())));
                 }
                 Statement::VariableReassignment { name, value} => {
-                    translations.append(&mut self.compile_expr_ast(value).iter().map(|x| Translation::Instruction(x.clone())).collect());
+                    translations.append(&mut self.compile_expr_ast(value));
                     translations.push(Translation::Instruction(Instruction::Store(name.clone())));
                 }
-                Statement::FunctionCall { name, args } => {
-                    let args_len = args.len();
-                    for arg in args {
-                        translations.append(&mut self.compile_expr_ast(arg).iter().map(|x| Translation::Instruction(x.clone())).collect())
+                Statement::IndexReassignment { name, mut indices, value } => {
+                    // xs[i][j] = v reads xs[i] and then writes into it, the inner list is shared so xs sees the change
+                    let last_index = indices.pop().expect("Grammar error: no index");
+                    translations.push(Translation::Instruction(Instruction::Load(name)));
+                    for index in indices {
+                        translations.append(&mut self.compile_expr_ast(index));
+                        translations.push(Translation::Instruction(Instruction::Index));
                     }
-                    translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(args_len as i32)))));
-                    translations.push(Translation::Instruction(Instruction::Call(name.clone())))
+                    translations.append(&mut self.compile_expr_ast(last_index));
+                    translations.append(&mut self.compile_expr_ast(value));
+                    translations.push(Translation::Instruction(Instruction::StoreIndex));
+                }
+                Statement::FunctionCall { name, args } => {
+                    translations.append(&mut self.compile_call(name, args));
                 }
                 Statement::Conditional { condition, body, else_if_conditions, else_body } => {
-                    translations.append(&mut self.compile_expr_ast(condition).iter().map(|x| Translation::Instruction(x.clone())).collect());
-                    let end_label = self.label_count;
+                    translations.append(&mut self.compile_expr_ast(condition));
+                    let conditional_end_label = self.label_count;
                     self.label_count += 1;
                     let body_end_label = self.label_count;
                     self.label_count += 1;
                     translations.push(Translation::Jump(2, body_end_label));
-                    for statement in self.compile_block(body, None, None, in_a_function){
+                    for statement in self.compile_block(body, start_label, end_label, in_a_function)?{
                         translations.push(statement)
                     }
-                    translations.push(Translation::Jump(0, end_label));
+                    translations.push(Translation::Jump(0, conditional_end_label));
                     translations.push(Translation::Label(body_end_label));
 
                     for (condition, body) in else_if_conditions {
-                        translations.append(&mut self.compile_expr_ast(condition).iter().map(|x| Translation::Instruction(x.clone())).collect());
+                        translations.push(Translation::Position(span));
+                        translations.append(&mut self.compile_expr_ast(condition));
                         let body_end_label = self.label_count;
                         self.label_count += 1;
                         translations.push(Translation::Jump(2, body_end_label));
-                        for statement in self.compile_block(body, None, None, in_a_function){
+                        for statement in self.compile_block(body, start_label, end_label, in_a_function)?{
                             translations.push(statement)
                         }
-                        translations.push(Translation::Jump(0, end_label));
+                        translations.push(Translation::Jump(0, conditional_end_label));
                         translations.push(Translation::Label(body_end_label));
                     }
-                    match else_body {
-                        Some(body) => {
-                            for statement in self.compile_block(body, None, None, false){
-                                translations.push(statement)
-                            }
+                    if let Some(body) = else_body {
+                        for statement in self.compile_block(body, start_label, end_label, in_a_function)?{
+                            translations.push(statement)
                         }
-                        None => {}
                     }
-                    translations.push(Translation::Label(end_label));
+                    translations.push(Translation::Label(conditional_end_label));
                 }
                 Statement::WhileLoop { condition, body } => {
                     let start_label = self.label_count;
@@ -209,86 +406,225 @@ impl InstructionCompiler {
                     let end_label = self.label_count;
                     self.label_count += 1;
                     translations.push(Translation::Label(start_label));
-                    translations.append(&mut self.compile_expr_ast(condition).iter().map(|x| Translation::Instruction(x.clone())).collect());
+                    translations.append(&mut self.compile_expr_ast(condition));
                     translations.push(Translation::Jump(2, end_label));
-                    for statement in self.compile_block(body, Some(start_label), Some(end_label), in_a_function){
+                    self.loop_scopes.push((self.live_variables.len(), self.try_scopes.len()));
+                    for statement in self.compile_block(body, Some(start_label), Some(end_label), in_a_function)?{
                         translations.push(statement)
                     }
+                    self.loop_scopes.pop();
                     translations.push(Translation::Jump(0, start_label));
                     translations.push(Translation::Label(end_label));
                 }
-                Statement::Continue => {
-                    match start_label {
-                        Some(label) => { translations.push(Translation::Jump(0, label)) }
-                        None => { panic!("continue statement outside of loop") }
-                    }
-                }
-                Statement::Break => {
-                    match end_label {
-                        Some(label) => { translations.push(Translation::Jump(0, label)) }
-                        None => { panic!("break statement outside of loop") }
+                Statement::ForLoop { var_name, start, end, body } => {
+                    // para i gikan start hangtod end, the end is evaluated once and is exclusive
+                    let start_label = self.label_count;
+                    self.label_count += 1;
+                    let step_label = self.label_count;
+                    self.label_count += 1;
+                    let end_label = self.label_count;
+                    self.label_count += 1;
+                    let end_name = format!("para@{}", end_label);
+
+                    translations.append(&mut self.compile_expr_ast(start));
+                    translations.push(Translation::Instruction(Instruction::NewVariable(var_name.clone())));
+                    translations.append(&mut self.compile_expr_ast(end));
+                    translations.push(Translation::Instruction(Instruction::NewVariable(end_name.clone())));
+
+                    translations.push(Translation::Label(start_label));
+                    translations.push(Translation::Instruction(Instruction::Load(var_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Load(end_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Lt));
+                    translations.push(Translation::Jump(2, end_label));
+                    self.loop_scopes.push((self.live_variables.len(), self.try_scopes.len()));
+                    for statement in self.compile_block(body, Some(step_label), Some(end_label), in_a_function)?{
+                        translations.push(statement)
                     }
+                    self.loop_scopes.pop();
+                    translations.push(Translation::Label(step_label));
+                    translations.push(Translation::Instruction(Instruction::Load(var_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int8(1)))));
+                    translations.push(Translation::Instruction(Instruction::Add));
+                    translations.push(Translation::Instruction(Instruction::Store(var_name.clone())));
+                    translations.push(Translation::Jump(0, start_label));
+                    translations.push(Translation::Label(end_label));
+                    translations.push(Translation::Instruction(Instruction::Delete(var_name)));
+                    translations.push(Translation::Instruction(Instruction::Delete(end_name)));
                 }
-                Statement::FunctionDefinition { name, args, mut body } => {
-                    if self.functions_locations.contains_key(&*name) {
-                        panic!("Function {} already defined", name)
-                    } else if self.class_details.contains_key(&*name) {
-                        panic!("Function {} already defined as a class", name)
+                Statement::ForEachLoop { var_name, iterable, body } => {
+                    // para x sa lista, walks a hidden index over the list that was there when the loop started
+                    let start_label = self.label_count;
+                    self.label_count += 1;
+                    let step_label = self.label_count;
+                    self.label_count += 1;
+                    let end_label = self.label_count;
+                    self.label_count += 1;
+                    let list_name = format!("para@{}", start_label);
+                    let index_name = format!("para@{}", step_label);
+
+                    translations.append(&mut self.compile_expr_ast(iterable));
+                    translations.push(Translation::Instruction(Instruction::Iterable));
+                    translations.push(Translation::Instruction(Instruction::NewVariable(list_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int8(0)))));
+                    translations.push(Translation::Instruction(Instruction::NewVariable(index_name.clone())));
+
+                    translations.push(Translation::Label(start_label));
+                    translations.push(Translation::Instruction(Instruction::Load(index_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Load(list_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(1)))));
+                    translations.push(Translation::Instruction(Instruction::Call("length".to_string())));
+                    translations.push(Translation::Instruction(Instruction::Lt));
+                    translations.push(Translation::Jump(2, end_label));
+
+                    self.loop_scopes.push((self.live_variables.len(), self.try_scopes.len()));
+                    self.live_variables.push(var_name.clone());
+                    translations.push(Translation::Instruction(Instruction::Load(list_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Load(index_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Index));
+                    translations.push(Translation::Instruction(Instruction::NewVariable(var_name.clone())));
+                    for statement in self.compile_block(body, Some(step_label), Some(end_label), in_a_function)?{
+                        translations.push(statement)
                     }
+                    self.live_variables.pop();
+                    self.loop_scopes.pop();
+                    translations.push(Translation::Instruction(Instruction::Delete(var_name)));
 
-                    let mut local_assignments = vec![];
-                    let end_label = self.label_count;
+                    translations.push(Translation::Label(step_label));
+                    translations.push(Translation::Instruction(Instruction::Load(index_name.clone())));
+                    translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int8(1)))));
+                    translations.push(Translation::Instruction(Instruction::Add));
+                    translations.push(Translation::Instruction(Instruction::Store(index_name.clone())));
+                    translations.push(Translation::Jump(0, start_label));
+                    translations.push(Translation::Label(end_label));
+                    translations.push(Translation::Instruction(Instruction::Delete(list_name)));
+                    translations.push(Translation::Instruction(Instruction::Delete(index_name)));
+                }
+                Statement::TryCatch { body, catch, finally } => {
+                    // a katapusan is a second handler around everything else, it runs the block and raises the error again
+                    let finally_label = self.label_count;
                     self.label_count += 1;
-                    translations.push(Translation::Jump(0, end_label));
-                    translations.push(Translation::Label(self.label_count));
-                    self.functions_label_locations.push((name.clone(), self.label_count));
+                    let end_label_of_try = self.label_count;
                     self.label_count += 1;
-                    translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(args.len() as i32)))));
-                    translations.push(Translation::Instruction(Instruction::Eq));
-                    translations.push(Translation::Instruction(Instruction::Push(ValueType::String(StringValue::new(format!("Error: Expected {} arguments", args.len()))))));
-                    translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(2)))));
-                    translations.push(Translation::Instruction(Instruction::Call("assert".to_string())));
-
-                    let mut args = args;
-                    args.reverse();
-                    for arg in args {
-                        translations.push(Translation::Instruction(Instruction::NewVariable(arg.clone())));
-                        local_assignments.push(arg.clone());
+                    if finally.is_some() {
+                        translations.push(Translation::Jump(3, finally_label));
+                        self.try_scopes.push(finally.clone());
+                    }
+
+                    match catch {
+                        Some((name, catch_body)) => {
+                            let catch_label = self.label_count;
+                            self.label_count += 1;
+                            let catch_end_label = self.label_count;
+                            self.label_count += 1;
+                            translations.push(Translation::Jump(3, catch_label));
+                            self.try_scopes.push(None);
+                            let body = self.compile_block(body, start_label, end_label, in_a_function);
+                            self.try_scopes.pop();
+                            translations.append(&mut body?);
+                            translations.push(Translation::Instruction(Instruction::TryEnd));
+                            translations.push(Translation::Jump(0, catch_end_label));
+
+                            translations.push(Translation::Label(catch_label));
+                            self.live_variables.push(name.clone());
+                            translations.push(Translation::Instruction(Instruction::NewVariable(name.clone())));
+                            translations.append(&mut self.compile_block(catch_body, start_label, end_label, in_a_function)?);
+                            self.live_variables.pop();
+                            translations.push(Translation::Instruction(Instruction::Delete(name)));
+                            translations.push(Translation::Label(catch_end_label));
+                        }
+                        None => {
+                            translations.append(&mut self.compile_block(body, start_label, end_label, in_a_function)?);
+                        }
                     }
-                    let mut compiled_body = self.compile_block(body.clone(), None, None, true);
 
-                    let mut add_at = vec![];
-                    for (i, instruction) in compiled_body.clone().iter().enumerate() {
-                        match instruction {
-                            Translation::Instruction(Instruction::Return) => {
-                                add_at.push(i);
+                    if let Some(finally) = finally {
+                        self.try_scopes.pop();
+                        translations.push(Translation::Instruction(Instruction::TryEnd));
+                        translations.append(&mut self.compile_block(finally.clone(), start_label, end_label, in_a_function)?);
+                        translations.push(Translation::Jump(0, end_label_of_try));
+
+                        let error_name = format!("sulayi@{}", finally_label);
+                        translations.push(Translation::Label(finally_label));
+                        self.live_variables.push(error_name.clone());
+                        translations.push(Translation::Instruction(Instruction::NewVariable(error_name.clone())));
+                        translations.append(&mut self.compile_block(finally, start_label, end_label, in_a_function)?);
+                        self.live_variables.pop();
+                        translations.push(Translation::Instruction(Instruction::Load(error_name.clone())));
+                        translations.push(Translation::Instruction(Instruction::Delete(error_name)));
+                        translations.push(Translation::Instruction(Instruction::Throw));
+                    }
+                    translations.push(Translation::Label(end_label_of_try));
+                }
+                Statement::Throw { value } => {
+                    translations.append(&mut self.compile_expr_ast(value));
+                    translations.push(Translation::Instruction(Instruction::Throw));
+                }
+                Statement::Continue => {
+                    match (start_label, self.loop_scopes.last().copied()) {
+                        (Some(label), Some((loop_start, loop_tries))) => {
+                            translations.append(&mut self.leave_try_scopes(loop_tries, start_label, end_label, in_a_function)?);
+                            for variable in self.live_variables[loop_start..].iter().rev() {
+                                translations.push(Translation::Instruction(Instruction::Delete(variable.clone())))
                             }
-                            _ => {}
+                            translations.push(Translation::Jump(0, label))
                         }
+                        _ => { return Err(Diagnostic::new(ErrorKind::ContinueOutsideLoop, Some(span))) }
                     }
-                    add_at.reverse();
-                    for assignment in local_assignments {
-                        for location in &add_at {
-                            compiled_body.insert(*location, Translation::Instruction(Instruction::Delete(assignment.clone())))
+                }
+                Statement::Break => {
+                    match (end_label, self.loop_scopes.last().copied()) {
+                        (Some(label), Some((loop_start, loop_tries))) => {
+                            translations.append(&mut self.leave_try_scopes(loop_tries, start_label, end_label, in_a_function)?);
+                            for variable in self.live_variables[loop_start..].iter().rev() {
+                                translations.push(Translation::Instruction(Instruction::Delete(variable.clone())))
+                            }
+                            translations.push(Translation::Jump(0, label))
                         }
+                        _ => { return Err(Diagnostic::new(ErrorKind::BreakOutsideLoop, Some(span))) }
                     }
-                    translations.append(&mut compiled_body);
-                    translations.push(Translation::Label(end_label));
+                }
+                Statement::FunctionDefinition { name, args, body } => {
+                    let name = self.qualify(&name);
+                    if self.functions_label_locations.iter().any(|(function, _)| *function == name) || self.class_details.contains_key(&*name) {
+                        return Err(Diagnostic::new(ErrorKind::AlreadyDefined(name), Some(span)))
+                    }
+
+                    let entry_label = self.label_count;
+                    self.label_count += 1;
+                    self.functions_label_locations.push((name, entry_label));
+                    translations.append(&mut self.compile_function(args, body, entry_label)?);
                 }
                 Statement::Return { returns } => {
                     if !in_a_function {
-                        panic!("return statement outside of function")
+                        return Err(Diagnostic::new(ErrorKind::ReturnOutsideFunction, Some(span)))
                     }
+                    // balik a, b gives back one list that deklara a, b = unpacks
+                    let count = returns.len();
                     for return_value in returns {
-                        translations.append(&mut self.compile_expr_ast(return_value).iter().map(|x| Translation::Instruction(x.clone())).collect());
+                        translations.append(&mut self.compile_expr_ast(return_value));
+                    }
+                    match count {
+                        0 => translations.push(Translation::Instruction(Instruction::Push(ValueType::Wala))),
+                        1 => {}
+                        _ => translations.push(Translation::Instruction(Instruction::BuildList(count))),
+                    }
+                    if self.try_scopes.iter().any(Option::is_some) {
+                        // the katapusan blocks run before returning and expect an empty stack, so the value waits in a variable
+                        let return_name = format!("balik@{}", self.label_count);
+                        self.label_count += 1;
+                        translations.push(Translation::Instruction(Instruction::NewVariable(return_name.clone())));
+                        translations.append(&mut self.leave_try_scopes(0, start_label, end_label, in_a_function)?);
+                        translations.push(Translation::Instruction(Instruction::Load(return_name.clone())));
+                        translations.push(Translation::Instruction(Instruction::Delete(return_name)));
+                    } else {
+                        translations.append(&mut self.leave_try_scopes(0, start_label, end_label, in_a_function)?);
                     }
                     translations.push(Translation::Instruction(Instruction::Return));
                 }
-                Statement::ClassDeclaration { class_name, members } => {
-                    if self.functions_locations.contains_key(&class_name) {
-                        panic!("Class {} already defined as a function", class_name)
-                    } else if self.class_details.contains_key(&class_name) {
-                        panic!("Class {} already defined", class_name)
+                Statement::ClassDeclaration { class_name, members, methods } => {
+                    let class_name = self.qualify(&class_name);
+                    if self.functions_label_locations.iter().any(|(function, _)| *function == class_name) || self.class_details.contains_key(&class_name) {
+                        return Err(Diagnostic::new(ErrorKind::AlreadyDefined(class_name), Some(span)))
                     }
 
                     let mut creator = ObjectCreator::new(class_name.clone());
@@ -298,32 +634,211 @@ impl InstructionCompiler {
 
                     self.class_details.insert(class_name.clone(), (members.len(), members));
                     self.class_creators.insert(class_name.clone(), creator);
+
+                    for (span, method) in methods {
+                        let span = Span { file: self.current_file, ..span };
+                        let Statement::FunctionDefinition { name, args, body } = method else {
+                            unreachable!("not a method: {:?}", method)
+                        };
+                        if self.methods_label_locations.iter().any(|(class, method, _)| *class == class_name && *method == name) {
+                            return Err(Diagnostic::new(ErrorKind::AlreadyDefined(format!("{}.{}", class_name, name)), Some(span)))
+                        }
+                        let entry_label = self.label_count;
+                        self.label_count += 1;
+                        self.methods_label_locations.push((class_name.clone(), name, entry_label));
+                        let mut method_args = vec!["kani".to_string()];
+                        method_args.extend(args);
+                        translations.push(Translation::Position(span));
+                        translations.append(&mut self.compile_function(method_args, body, entry_label)?);
+                    }
+                }
+                Statement::Import { modules } => {
+                    for module in modules {
+                        translations.append(&mut self.import(&module, span)?);
+                    }
                 }
                 Statement::VariableMultiAssignment { variables, value } => {
-                    translations.append(&mut self.compile_expr_ast(value).iter().map(|x| Translation::Instruction(x.clone())).collect());
+                    translations.append(&mut self.compile_expr_ast(value));
+                    translations.push(Translation::Instruction(Instruction::Unpack(variables.len())));
                     for variable in variables {
                         translations.push(Translation::Instruction(Instruction::NewVariable(variable.clone())));
-                        assignments.push(variable.clone());
+                        self.live_variables.push(variable.clone());
                     }
                 }
-                _ => { unimplemented!("statement {:?}: not implemented", statement) }
+            }
+            // nobody is going to use what a statement leaves behind, like the value of a call
+            for _ in 0..values_left(&translations[statement_start..]) {
+                translations.push(Translation::Instruction(Instruction::Pop));
             }
         }
-        for assignment in assignments {
+        for assignment in self.live_variables.split_off(scope_start) {
             translations.push(Translation::Instruction(Instruction::Delete(assignment)))
         }
+        Ok(translations)
+    }
+    /// the name a call or definition refers to once namespaces are applied, a module's own proseso and klase
+    /// live under its namespace and `utils.f` becomes whatever namespace the utils module was compiled under
+    fn qualify(&self, name: &str) -> String {
+        if let Some(namespace) = self.namespace.as_ref().filter(|_| self.module_names.contains(name)) {
+            return format!("{}.{}", namespace, name);
+        }
+        match name.rsplit_once('.') {
+            Some((module, name)) if self.imports.contains_key(module) => format!("{}.{}", self.imports[module], name),
+            _ => name.to_string(),
+        }
+    }
+    /// a call by name, `random.numero(...)` only means the native function once random is imported,
+    /// before that it is a method on whatever `random` is in scope
+    fn compile_call(&mut self, name: String, args: Vec<ExprAst>) -> Vec<Translation> {
+        let mut inst = vec![];
+        let method = name.split_once('.')
+            .filter(|(module, method)| NATIVE_MODULES.contains(module) && !self.imports.contains_key(*module) && !method.contains('.'));
+        if let Some((receiver, _)) = method {
+            inst.push(Translation::Instruction(Instruction::Load(receiver.to_string())));
+        }
+        let args_len = args.len();
+        for arg in args {
+            inst.append(&mut self.compile_expr_ast(arg))
+        }
+        inst.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(args_len as i32)))));
+        match method {
+            Some((_, method)) => inst.push(Translation::Instruction(Instruction::CallMethod(method.to_string()))),
+            None => inst.push(Translation::Instruction(Instruction::Call(self.qualify(&name)))),
+        }
+        inst
+    }
+    /// compiles the module `lib.utils` from lib/utils.bis next to the current file, once no matter how often it is imported,
+    /// native modules like random are already in the vm and only need their name
+    fn import(&mut self, module: &str, span: Span) -> Result<Vec<Translation>, Diagnostic> {
+        if NATIVE_MODULES.contains(&module) {
+            self.imports.insert(module.to_string(), module.to_string());
+            return Ok(vec![])
+        }
+        let directory = self.files[self.current_file as usize].parent().map(Path::to_path_buf).unwrap_or_default();
+        let relative = format!("{}.bis", module.replace('.', "/"));
+        let Ok(path) = directory.join(&relative).canonicalize() else {
+            return Err(Diagnostic::new(ErrorKind::ModuleNotFound(relative), Some(span)))
+        };
+        if let Some(position) = self.importing.iter().position(|(importing, _)| *importing == path) {
+            let mut chain: Vec<String> = self.importing[position..].iter().map(|(_, name)| name.clone()).collect();
+            chain.push(self.importing[position].1.clone());
+            return Err(Diagnostic::new(ErrorKind::ImportCycle(chain), Some(span)))
+        }
+        if let Some(namespace) = self.modules.get(&path) {
+            self.imports.insert(module.to_string(), namespace.clone());
+            return Ok(vec![])
+        }
+        let Ok(source) = fs::read_to_string(&path) else {
+            return Err(Diagnostic::new(ErrorKind::ModuleNotFound(relative), Some(span)))
+        };
+        // modules are named after where they are from the file that was run, so two utils.bis in different folders don't clash
+        let root = self.files[0].parent().unwrap_or(Path::new(""));
+        let namespace = path.strip_prefix(root).unwrap_or(&path).with_extension("")
+            .components().map(|part| part.as_os_str().to_string_lossy()).collect::<Vec<_>>().join(".");
+
+        let file = self.files.len() as u32;
+        self.files.push(path.clone());
+        let statements = parse_file_data(&source).map_err(|mut error| {
+            error.span = error.span.map(|span| Span { file, ..span });
+            error
+        })?;
+        let mut module_names = HashSet::new();
+        for (statement_span, statement) in &statements {
+            match statement {
+                Statement::FunctionDefinition { name, .. } | Statement::ClassDeclaration { class_name: name, .. } => {
+                    module_names.insert(name.clone());
+                }
+                Statement::Import { .. } => {}
+                _ => return Err(Diagnostic::new(ErrorKind::NotAllowedInModule, Some(Span { file, ..*statement_span })))
+            }
+        }
+
+        self.importing.push((path.clone(), namespace.clone()));
+        let outer_file = std::mem::replace(&mut self.current_file, file);
+        let outer_namespace = self.namespace.replace(namespace.clone());
+        let outer_names = std::mem::replace(&mut self.module_names, module_names);
+        let outer_imports = std::mem::take(&mut self.imports);
+        let translations = self.compile_block(statements, None, None, false);
+        self.importing.pop();
+        self.current_file = outer_file;
+        self.namespace = outer_namespace;
+        self.module_names = outer_names;
+        self.imports = outer_imports;
+
+        self.modules.insert(path, namespace.clone());
+        self.imports.insert(module.to_string(), namespace);
         translations
     }
+    /// pops every sulayi handler entered since there were `depth` of them, running the katapusan blocks on the way out,
+    /// for buwag, tiwas and balik jumping out of the middle of a sulayi
+    fn leave_try_scopes(&mut self, depth: usize, start_label: Option<usize>, end_label: Option<usize>, in_a_function: bool) -> Result<Vec<Translation>, Diagnostic> {
+        let mut translations = vec![];
+        let scopes = self.try_scopes.clone();
+        let mut result = Ok(());
+        for scope in (depth..scopes.len()).rev() {
+            // a katapusan runs outside of its own handler, an error in it goes to the ones around it
+            self.try_scopes.truncate(scope);
+            translations.push(Translation::Instruction(Instruction::TryEnd));
+            if let Some(finally) = &scopes[scope] {
+                match self.compile_block(finally.clone(), start_label, end_label, in_a_function) {
+                    Ok(mut finally) => translations.append(&mut finally),
+                    Err(error) => { result = Err(error); break }
+                }
+            }
+        }
+        self.try_scopes = scopes;
+        result.map(|_| translations)
+    }
+    /// a proseso body that is jumped over where it is defined and entered at entry_label by Call
+    fn compile_function(&mut self, args: Vec<String>, body: Block, entry_label: usize) -> Result<Vec<Translation>, Diagnostic> {
+        let mut translations = vec![];
+        let end_label = self.label_count;
+        self.label_count += 1;
+        translations.push(Translation::Jump(0, end_label));
+        translations.push(Translation::Label(entry_label));
+        translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(args.len() as i32)))));
+        translations.push(Translation::Instruction(Instruction::Eq));
+        translations.push(Translation::Instruction(Instruction::Push(ValueType::String(StringValue::new(format!("Error: Expected {} arguments", args.len()))))));
+        translations.push(Translation::Instruction(Instruction::Push(ValueType::Int(IntValue::Int32(2)))));
+        translations.push(Translation::Instruction(Instruction::Call("assert".to_string())));
+        translations.push(Translation::Instruction(Instruction::Pop));
+
+        let mut args = args;
+        args.reverse();
+        // arguments live in the call frame the vm pushes on Call, and are dropped with it on Return
+        for arg in args {
+            translations.push(Translation::Instruction(Instruction::NewVariable(arg.clone())));
+        }
+        // the body runs in its own frame, so the enclosing blocks' variables and loops don't apply to it
+        let outer_variables = std::mem::take(&mut self.live_variables);
+        let outer_loops = std::mem::take(&mut self.loop_scopes);
+        let outer_tries = std::mem::take(&mut self.try_scopes);
+        let body = self.compile_block(body, None, None, true);
+        self.live_variables = outer_variables;
+        self.loop_scopes = outer_loops;
+        self.try_scopes = outer_tries;
+        translations.append(&mut body?);
+        // falling off the end of the body gives back wala instead of running into whatever comes after the proseso
+        translations.push(Translation::Instruction(Instruction::Push(ValueType::Wala)));
+        translations.push(Translation::Instruction(Instruction::Return));
+        translations.push(Translation::Label(end_label));
+        Ok(translations)
+    }
     fn compile_translation(&mut self, translations: Vec<Translation>) -> Vec<Instruction>{
         let mut instructions = vec![];
         let mut last_iterations = vec![];
+        let mut position = Span::default();
         for translation in translations {
             // println!("{:?}", translation);
             match translation {
                 Translation::Label(label_id) => {
                     self.labels.insert(label_id, last_iterations.len() + 1);
                 }
+                Translation::Position(span) => {
+                    position = span;
+                }
                 _ => {
+                    self.source_map.push(position);
                     last_iterations.push(translation)
                 }
             }
@@ -347,32 +862,98 @@ impl InstructionCompiler {
                         2 => {
                             instructions.push(Instruction::JumpIfFalse(label_line))
                         }
+                        3 => {
+                            instructions.push(Instruction::TryBegin(label_line))
+                        }
                         _ => { unreachable!("rust wtf") }
                     }
                 }
-                Translation::Label(_) => {
+                Translation::Label(_) | Translation::Position(_) => {
                     unreachable!("rust wtf")
                 }
             }
         }
         instructions
     }
-    pub fn run(&mut self, statements: Vec<Statement>) {
-        let translations = self.compile_block(statements, None, None, false);
+    pub fn run(&mut self, statements: Block) -> Result<(), Diagnostic> {
+        let translations = self.compile_block(statements, None, None, false)?;
         let mut compiled = self.compile_translation(translations);
         self.instructions.append(&mut compiled);
         for label in self.functions_label_locations.clone() {
             let label_line = *self.labels.get(&label.1).unwrap_or_else(|| panic!("label {} not found", label.1));
             self.functions_locations.insert(label.0, label_line );
         }
+        for (class_name, method_name, label) in self.methods_label_locations.clone() {
+            let label_line = *self.labels.get(&label).unwrap_or_else(|| panic!("label {} not found", label));
+            self.class_creators.get_mut(&class_name).unwrap().add_method(method_name, label_line);
+        }
+        Ok(())
     }
-    pub fn compile(vec: Vec<Statement>) -> (Vec<Instruction>, HashMap<String, usize>, HashMap<String, ObjectCreator>){
+    /// compiles the file at `path` along with every module it imports, `files` gets the source files
+    /// that Span::file points into even when compiling fails
+    pub fn compile(vec: Block, path: &Path, files: &mut Vec<PathBuf>) -> Result<CompiledProgram, Diagnostic> {
         let mut compiler = InstructionCompiler::new();
-        compiler.run(vec);
-        (compiler.instructions, compiler.functions_locations, compiler.class_creators)
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        compiler.files.push(path.clone());
+        compiler.importing.push((path, name));
+        let result = compiler.run(vec);
+        *files = std::mem::take(&mut compiler.files);
+        result?;
+        Ok((compiler.instructions, compiler.functions_locations, compiler.class_creators, compiler.source_map))
     }
 }
 
-pub fn compile(vec: Vec<Statement>) -> (Vec<Instruction>, HashMap<String, usize>, HashMap<String, ObjectCreator>) {
-    InstructionCompiler::compile(vec)
-}
\ No newline at end of file
+pub fn compile(vec: Block, path: &Path, files: &mut Vec<PathBuf>) -> Result<CompiledProgram, Diagnostic> {
+    InstructionCompiler::compile(vec, path, files)
+}
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::parser::parse_file_data;
+    use crate::virtual_machine::VirtualMachine;
+    use super::*;
+
+    /// writes the files into a fresh directory and compiles the first one
+    fn compile_files(test: &str, sources: &[(&str, &str)]) -> (Result<CompiledProgram, Diagnostic>, Vec<PathBuf>) {
+        let directory = std::env::temp_dir().join(format!("bisaya-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        for (name, source) in sources {
+            let path = directory.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        let mut files = vec![];
+        let statements = parse_file_data(sources[0].1).unwrap();
+        let result = compile(statements, &directory.join(sources[0].0), &mut files);
+        fs::remove_dir_all(&directory).unwrap();
+        (result, files)
+    }
+
+    #[test]
+    fn imports_modules_under_their_namespace_once() {
+        let (program, files) = compile_files("imports", &[
+            ("main.bis", "ipasulod { lib.utils, lib.helper }\nassert(lib.utils.double(21) == 42, \"double\")\n"),
+            ("lib/utils.bis", "ipasulod { helper }\nproseso double(x) {\n    balik helper.add(x, x)\n}\n"),
+            ("lib/helper.bis", "proseso add(a, b) {\n    balik a + b\n}\n"),
+        ]);
+        let (instructions, functions, classes, source_map) = program.unwrap();
+        assert_eq!(files.len(), 3, "helper is compiled once even though it is imported twice");
+        assert!(functions.contains_key("lib.utils.double") && functions.contains_key("lib.helper.add"));
+        VirtualMachine::new(instructions, functions, classes, source_map).run().unwrap();
+    }
+
+    #[test]
+    fn import_errors() {
+        let (result, _) = compile_files("missing", &[("main.bis", "ipasulod { wala_ni }\n")]);
+        assert!(matches!(result.unwrap_err().kind, ErrorKind::ModuleNotFound(name) if name == "wala_ni.bis"));
+
+        let (result, _) = compile_files("cycle", &[("a.bis", "ipasulod { b }\n"), ("b.bis", "ipasulod { a }\n")]);
+        assert!(matches!(result.unwrap_err().kind, ErrorKind::ImportCycle(chain) if chain.len() == 3), "a -> b -> a");
+
+        let (result, files) = compile_files("statement", &[("main.bis", "ipasulod { lib }\n"), ("lib.bis", "println(1)\n")]);
+        let diagnostic = result.unwrap_err();
+        assert!(matches!(diagnostic.kind, ErrorKind::NotAllowedInModule));
+        assert!(files[diagnostic.span.unwrap().file as usize].ends_with("lib.bis"), "the error points into the module");
+    }
+}
@@ -1,8 +1,18 @@
 mod parser;
 mod eval_parser;
+// the tree-walking interpreter the virtual machine replaced, nothing runs it but it is kept
+// working as a reference for what each node means
+#[allow(dead_code)]
 mod node_runner;
 mod virtual_machine;
 mod instruction_compiler;
+mod diagnostics;
+mod garbage_collector;
+mod random;
+mod format_spec;
+mod verifier;
+mod bytecode_file;
+mod disassembler;
 
 extern crate pest;
 #[macro_use]
@@ -10,44 +20,127 @@ extern crate pest_derive;
 
 use std::collections::VecDeque;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::exit;
-use pest::Parser;
-use crate::parser::{Rule, BareParser, parse_file_data, Statement};
-use crate::eval_parser::{ExprAst, parse_expressions};
-use crate::node_runner::NodeRunner;
-use crate::virtual_machine::{Instruction, IntValue, ObjectCreator, Value, ValueType, VirtualMachine};
+use crate::diagnostics::{Diagnostic, Language};
+use crate::parser::parse_file_data;
+use crate::virtual_machine::VirtualMachine;
+
+fn report(diagnostic: Diagnostic, source: &str, language: Language) -> ! {
+    eprintln!("{}", diagnostic.render(source, None, language));
+    exit(1)
+}
+
+/// like report, but shows the imported module the error happened in instead of the file that was run
+fn report_in(diagnostic: Diagnostic, files: &[PathBuf], source: &str, language: Language) -> ! {
+    let module = diagnostic.span.filter(|span| span.file != 0).map(|span| files[span.file as usize].as_path());
+    let Some(module) = module else { report(diagnostic, source, language) };
+    let module_source = fs::read_to_string(module).unwrap_or_default();
+    eprintln!("{}", diagnostic.render(&module_source, Some(module), language));
+    exit(1)
+}
+
+fn usage(executable_path: &str) -> String {
+    format!(
+        "Usage: {0} <file.bis | file.bisc> [--debug] [--english] [--gc-trace]\n       {0} build <file.bis> [-o <file.bisc>] [--english]\n       {0} disasm <file.bis | file.bisc> [--english]",
+        executable_path
+    )
+}
+
+/// a command line that can't be run, says what is wrong and how it should look
+fn usage_error(executable_path: &str, problem: &str) -> ! {
+    eprintln!("{}\n{}", problem, usage(executable_path));
+    exit(2)
+}
 
 fn main() {
     let mut debug_mode = false;
-    let mut args: VecDeque<String> = std::env::args().into_iter().collect();
-    let _executable_path = args.pop_front().expect("impossible");
-    let file_name = args.pop_front().expect("no file name given");
+    let mut language = Language::Bisaya;
+    let mut gc_trace = false;
+    let mut output = None;
+    let mut args: VecDeque<String> = std::env::args().collect();
+    let executable_path = args.pop_front().unwrap_or_else(|| "bisaya_lang".to_string());
+    let Some(mut file_name) = args.pop_front() else { usage_error(&executable_path, "no file name given") };
+    let building = file_name == "build";
+    let disassembling = file_name == "disasm";
+    if building || disassembling {
+        let Some(name) = args.pop_front() else { usage_error(&executable_path, &format!("no file name given to {}", file_name)) };
+        file_name = name;
+    }
     if file_name == "--help" || file_name == "-h" {
-        println!("Usage: {} <file> [--debug]", _executable_path);
+        println!("{}", usage(&executable_path));
         return;
     }
-    for arg in args {
+    while let Some(arg) = args.pop_front() {
         match &*arg {
+            // disasm only lists the program, it never runs it
+            "--debug" | "-d" | "--gc-trace" if disassembling => usage_error(&executable_path, &format!("{} can't be used with disasm", arg)),
             "--debug" | "-d" => debug_mode = true,
-            "--help"  | "-h"=> { println!("Usage: {} <file> [--debug]", _executable_path); exit(0) },
-            _ => unimplemented!("{} has not been implemented or its invalid", arg)
+            "--english" | "-e" => language = Language::English,
+            "--gc-trace" => gc_trace = true,
+            "--output" | "-o" if building => match args.pop_front() {
+                Some(name) => output = Some(name),
+                None => usage_error(&executable_path, &format!("no file name given after {}", arg)),
+            },
+            "--help"  | "-h"=> { println!("{}", usage(&executable_path)); exit(0) },
+            _ => usage_error(&executable_path, &format!("unknown option {}", arg)),
         }
     }
-    let file_contents = fs::read_to_string(file_name).expect("couldnt read file");
-    let statements = parse_file_data(&file_contents);
-    let (instructions, function_locations, class_creators) =  instruction_compiler::compile(statements.clone());
-
-    if debug_mode {
-        println!("{:?}", file_contents);
-        println!("------ Parsed Statements ----------");
-        println!("{:#?}", statements);
-        println!("------ Compiled Instruction ----------");
-        for instruction in &instructions {
-            println!("{:?}", instruction)
+
+    let compiled = Path::new(&file_name).extension().is_some_and(|extension| extension == "bisc");
+    let (program, files, file_contents) = if compiled {
+        let bytes = fs::read(&file_name).expect("couldnt read file");
+        let (program, files) = bytecode_file::load(&bytes).unwrap_or_else(|e| {
+            eprintln!("{}", e.render("", Some(Path::new(&file_name)), language));
+            exit(1)
+        });
+        // the sources are only for pointing at the line of an error, the program runs without them
+        let file_contents = files.first().and_then(|file| fs::read_to_string(file).ok()).unwrap_or_default();
+        (program, files, file_contents)
+    } else {
+        let file_contents = fs::read_to_string(&file_name).expect("couldnt read file");
+        let statements = parse_file_data(&file_contents).unwrap_or_else(|e| report(e, &file_contents, language));
+        if debug_mode {
+            println!("{:?}", file_contents);
+            println!("------ Parsed Statements ----------");
+            println!("{:#?}", statements);
         }
+        let mut files = vec![];
+        let program = instruction_compiler::compile(statements, Path::new(&file_name), &mut files)
+            .unwrap_or_else(|e| report_in(e, &files, &file_contents, language));
+        (program, files, file_contents)
+    };
+    let (instructions, function_locations, class_creators, source_map) = &program;
+    verifier::verify(instructions, function_locations, class_creators, source_map)
+        .unwrap_or_else(|e| report_in(e, &files, &file_contents, language));
+
+    if building {
+        let output = output.map_or_else(|| Path::new(&file_name).with_extension("bisc"), PathBuf::from);
+        let bytes = bytecode_file::save(&program, &files).unwrap_or_else(|e| report(e, &file_contents, language));
+        fs::write(&output, bytes).expect("couldnt write the .bisc file");
+        return;
+    }
+
+    if disassembling || debug_mode {
+        let sources: Vec<String> = files.iter().enumerate()
+            .map(|(index, file)| if index == 0 { file_contents.clone() } else { fs::read_to_string(file).unwrap_or_default() })
+            .collect();
+        let listing = disassembler::disassemble(&program, &files, &sources);
+        if disassembling {
+            print!("{}", listing);
+            return;
+        }
+        println!("------ Compiled Instruction ----------");
+        print!("{}", listing);
         println!("------ Virtual Machine Output ----------");
     }
 
-    let mut vm = VirtualMachine::new(instructions, function_locations, class_creators);
-    vm.run();
-}
\ No newline at end of file
+    let (instructions, function_locations, class_creators, source_map) = program;
+    let mut vm = VirtualMachine::new(instructions, function_locations, class_creators, source_map);
+    vm.trace_gc(gc_trace);
+    vm.set_language(language);
+    vm.set_debug(debug_mode);
+    if let Err(error) = vm.run() {
+        report_in(error.into(), &files, &file_contents, language)
+    }
+}
@@ -1,10 +1,34 @@
 use std::collections::{HashMap, VecDeque};
+use num_bigint::BigInt;
+use crate::diagnostics::{ErrorKind, Language};
 use crate::eval_parser::{ExprAst, EvalValue};
+use crate::format_spec::{self, FormatArgument};
 use crate::parser::{Statement, Block};
 
 const LOOP_LIMIT: usize = 100000;
 
 
+impl FormatArgument for EvalValue {
+    fn display(&self) -> String {
+        self.to_string()
+    }
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            EvalValue::IntegerLiteral { val } | EvalValue::FloatLiteral { val } => val.parse().ok(),
+            _ => None,
+        }
+    }
+    fn as_integer(&self) -> Option<BigInt> {
+        match self {
+            EvalValue::IntegerLiteral { val } => val.parse().ok(),
+            _ => None,
+        }
+    }
+    fn named(&self, _name: &str) -> Option<Self> {
+        None
+    }
+}
+
 pub struct NodeRunner {
     locals: HashMap<String, EvalValue>,
     locals_tracker: Vec<String>,
@@ -34,6 +58,8 @@ impl NodeRunner {
             EvalValue::FloatLiteral { .. } => { 3 }
             EvalValue::BooleanLiteral { .. } => { 4 }
             EvalValue::List { .. } => { 5 }
+            EvalValue::Map { .. } => { 6 }
+            EvalValue::Wala => { 7 }
         }
     }
 
@@ -52,17 +78,128 @@ impl NodeRunner {
             ExprAst::FunctionCall { name, args } => {
                 match self.handle_function_call(name, args) {
                     Some(val) => { val }
-                    None => { EvalValue::Stringliteral { val: "NONE".to_string() } }
+                    None => { EvalValue::Wala }
+                }
+            }
+            ExprAst::Index { target, index } => {
+                let list = match self.evaluate_expr(*target) {
+                    EvalValue::List { val } => val,
+                    val => panic!("cannot index into {}", val)
+                };
+                let index = self.evaluate_expr(*index).get_int();
+                let position = if index < 0 { index + list.len() as i32 } else { index };
+                match usize::try_from(position).ok().and_then(|position| list.get(position)) {
+                    Some(item) => self.evaluate_expr(item.clone()),
+                    None => panic!("index {} is out of range for a list of length {}", index, list.len())
+                }
+            }
+            ExprAst::And { lhs, rhs } => {
+                let val = self.evaluate_expr(*lhs).get_bool() && self.evaluate_expr(*rhs).get_bool();
+                EvalValue::BooleanLiteral { val }
+            }
+            ExprAst::Or { lhs, rhs } => {
+                let val = self.evaluate_expr(*lhs).get_bool() || self.evaluate_expr(*rhs).get_bool();
+                EvalValue::BooleanLiteral { val }
+            }
+            ExprAst::Not { expr } => {
+                EvalValue::BooleanLiteral { val: !self.evaluate_expr(*expr).get_bool() }
+            }
+            ExprAst::MethodCall { .. } | ExprAst::MemberAccess { .. } => {
+                unimplemented!("classes are only supported by the virtual machine")
+            }
+            ExprAst::Negate { expr } => {
+                match self.evaluate_expr(*expr) {
+                    EvalValue::IntegerLiteral { val } => EvalValue::IntegerLiteral { val: (-val.parse::<i32>().unwrap()).to_string() },
+                    EvalValue::FloatLiteral { val } => EvalValue::FloatLiteral { val: (-val.parse::<f32>().unwrap()).to_string() },
+                    val => unreachable!("cannot negate {}", val)
+                }
+            }
+            ExprAst::Modulo { lhs, rhs } => {
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
+
+                let lhs_type = self.get_value_type(&lhs);
+                let rhs_type = self.get_value_type(&rhs);
+
+                // the result takes the sign of the divisor, like the virtual machine
+                match (lhs_type, rhs_type) {
+                    (1, 1) => { EvalValue::IntegerLiteral { val: floor_rem(lhs.get_int(), rhs.get_int()).to_string() } }
+                    (3, 3) => { EvalValue::FloatLiteral { val: floor_rem_float(lhs.get_float(), rhs.get_float()).to_string() } }
+                    (1, 3) => { EvalValue::FloatLiteral { val: floor_rem_float(lhs.get_int() as f32, rhs.get_float()).to_string() } }
+                    (3, 1) => { EvalValue::FloatLiteral { val: floor_rem_float(lhs.get_float(), rhs.get_int() as f32).to_string() } }
+                    _ => unreachable!("invalid types for modulo")
+                }
+            }
+            ExprAst::FloorDivision { lhs, rhs } => {
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
+
+                let lhs_type = self.get_value_type(&lhs);
+                let rhs_type = self.get_value_type(&rhs);
+
+                match (lhs_type, rhs_type) {
+                    (1, 1) => {
+                        let (lhs, rhs) = (lhs.get_int(), rhs.get_int());
+                        EvalValue::IntegerLiteral { val: ((lhs - floor_rem(lhs, rhs)) / rhs).to_string() }
+                    }
+                    (3, 3) => { EvalValue::FloatLiteral { val: (lhs.get_float() / rhs.get_float()).floor().to_string() } }
+                    (1, 3) => { EvalValue::FloatLiteral { val: (lhs.get_int() as f32 / rhs.get_float()).floor().to_string() } }
+                    (3, 1) => { EvalValue::FloatLiteral { val: (lhs.get_float() / rhs.get_int() as f32).floor().to_string() } }
+                    _ => unreachable!("invalid types for floor division")
                 }
             }
+            ExprAst::Power { lhs, rhs } => {
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
+
+                let lhs_type = self.get_value_type(&lhs);
+                let rhs_type = self.get_value_type(&rhs);
+
+                match (lhs_type, rhs_type) {
+                    (1, 1) if rhs.get_int() < 0 => {
+                        EvalValue::FloatLiteral { val: (lhs.get_int() as f32).powi(rhs.get_int()).to_string() }
+                    }
+                    (1, 1) => { EvalValue::IntegerLiteral { val: lhs.get_int().pow(rhs.get_int() as u32).to_string() } }
+                    (3, 3) => { EvalValue::FloatLiteral { val: lhs.get_float().powf(rhs.get_float()).to_string() } }
+                    (1, 3) => { EvalValue::FloatLiteral { val: (lhs.get_int() as f32).powf(rhs.get_float()).to_string() } }
+                    (3, 1) => { EvalValue::FloatLiteral { val: lhs.get_float().powi(rhs.get_int()).to_string() } }
+                    _ => unreachable!("invalid types for power")
+                }
+            }
+            ExprAst::BitAnd { lhs, rhs } => {
+                let val = self.evaluate_expr(*lhs).get_int() & self.evaluate_expr(*rhs).get_int();
+                EvalValue::IntegerLiteral { val: val.to_string() }
+            }
+            ExprAst::BitOr { lhs, rhs } => {
+                let val = self.evaluate_expr(*lhs).get_int() | self.evaluate_expr(*rhs).get_int();
+                EvalValue::IntegerLiteral { val: val.to_string() }
+            }
+            ExprAst::BitXor { lhs, rhs } => {
+                let val = self.evaluate_expr(*lhs).get_int() ^ self.evaluate_expr(*rhs).get_int();
+                EvalValue::IntegerLiteral { val: val.to_string() }
+            }
+            ExprAst::ShiftLeft { lhs, rhs } => {
+                let (lhs, amount) = (self.evaluate_expr(*lhs).get_int(), self.evaluate_expr(*rhs).get_int());
+                let val = u32::try_from(amount).ok().and_then(|amount| lhs.checked_mul(2i32.checked_pow(amount)?))
+                    .unwrap_or_else(|| panic!("{} << {} overflows", lhs, amount));
+                EvalValue::IntegerLiteral { val: val.to_string() }
+            }
+            ExprAst::ShiftRight { lhs, rhs } => {
+                let (lhs, amount) = (self.evaluate_expr(*lhs).get_int(), self.evaluate_expr(*rhs).get_int());
+                let amount = u32::try_from(amount).unwrap_or_else(|_| panic!("negative shift count {}", amount));
+                EvalValue::IntegerLiteral { val: (lhs >> amount.min(i32::BITS - 1)).to_string() }
+            }
+            ExprAst::BitNot { expr } => {
+                EvalValue::IntegerLiteral { val: (!self.evaluate_expr(*expr).get_int()).to_string() }
+            }
             ExprAst::Addition { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::IntegerLiteral { val: (lhs.get_int() + rhs.get_int()).to_string() } }
                     (2, 2) => { EvalValue::Stringliteral { val: lhs.get_string() + &rhs.get_string() } }
                     (3, 3) => { EvalValue::FloatLiteral { val: (lhs.get_float() + rhs.get_float()).to_string() } }
@@ -72,13 +209,13 @@ impl NodeRunner {
                 }
             }
             ExprAst::Subtraction { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::IntegerLiteral { val: (lhs.get_int() - rhs.get_int()).to_string() } }
                     (3, 3) => { EvalValue::FloatLiteral { val: (lhs.get_float() - rhs.get_float()).to_string() } }
                     (1, 3) => { EvalValue::FloatLiteral { val: (lhs.get_int() as f32 - rhs.get_float()).to_string() } }
@@ -87,13 +224,13 @@ impl NodeRunner {
                 }
             }
             ExprAst::Division { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::FloatLiteral { val: (lhs.get_int() as f32 / rhs.get_int() as f32).to_string() } }
                     (3, 3) => { EvalValue::FloatLiteral { val: (lhs.get_float() / rhs.get_float()).to_string() } }
                     (1, 3) => { EvalValue::FloatLiteral { val: (lhs.get_int() as f32 / rhs.get_float()).to_string() } }
@@ -102,13 +239,13 @@ impl NodeRunner {
                 }
             }
             ExprAst::Multiplication { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::IntegerLiteral { val: (lhs.get_int() * rhs.get_int()).to_string() } }
                     (3, 3) => { EvalValue::FloatLiteral { val: (lhs.get_float() * rhs.get_float()).to_string() } }
                     (1, 3) => { EvalValue::FloatLiteral { val: (lhs.get_int() as f32 * rhs.get_float()).to_string() } }
@@ -117,45 +254,47 @@ impl NodeRunner {
                 }
             }
             ExprAst::Eq { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::BooleanLiteral { val: lhs.get_int() == rhs.get_int() } }
                     (2, 2) => { EvalValue::BooleanLiteral { val: lhs.get_string() == rhs.get_string() } }
                     (3, 3) => { EvalValue::BooleanLiteral { val: lhs.get_float() == rhs.get_float() } }
                     (1, 3) => { EvalValue::BooleanLiteral { val: lhs.get_int() as f32 == rhs.get_float() } }
                     (3, 1) => { EvalValue::BooleanLiteral { val: lhs.get_float() == rhs.get_int() as f32 } }
+                    (7, _) | (_, 7) => { EvalValue::BooleanLiteral { val: lhs_type == rhs_type } }
                     _ => unreachable!("invalid types for subtraction")
                 }
             }
             ExprAst::Neq { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::BooleanLiteral { val: lhs.get_int() != rhs.get_int() } }
                     (2, 2) => { EvalValue::BooleanLiteral { val: lhs.get_string() != rhs.get_string() } }
                     (3, 3) => { EvalValue::BooleanLiteral { val: lhs.get_float() != rhs.get_float() } }
                     (1, 3) => { EvalValue::BooleanLiteral { val: lhs.get_int() as f32 != rhs.get_float() } }
                     (3, 1) => { EvalValue::BooleanLiteral { val: lhs.get_float() != rhs.get_int() as f32 } }
+                    (7, _) | (_, 7) => { EvalValue::BooleanLiteral { val: lhs_type != rhs_type } }
                     _ => unreachable!("invalid types for subtraction")
                 }
             }
             ExprAst::GtEq { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::BooleanLiteral { val: lhs.get_int() >= rhs.get_int() } }
                     (3, 3) => { EvalValue::BooleanLiteral { val: lhs.get_float() >= rhs.get_float() } }
                     (1, 3) => { EvalValue::BooleanLiteral { val: lhs.get_int() as f32 >= rhs.get_float() } }
@@ -164,13 +303,13 @@ impl NodeRunner {
                 }
             }
             ExprAst::LtEq { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::BooleanLiteral { val: lhs.get_int() <= rhs.get_int() } }
                     (3, 3) => { EvalValue::BooleanLiteral { val: lhs.get_float() <= rhs.get_float() } }
                     (1, 3) => { EvalValue::BooleanLiteral { val: lhs.get_int() as f32 <= rhs.get_float() } }
@@ -179,13 +318,13 @@ impl NodeRunner {
                 }
             }
             ExprAst::Gt { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::BooleanLiteral { val: lhs.get_int() > rhs.get_int() } }
                     (3, 3) => { EvalValue::BooleanLiteral { val: lhs.get_float() > rhs.get_float() } }
                     (1, 3) => { EvalValue::BooleanLiteral { val: lhs.get_int() as f32 > rhs.get_float() } }
@@ -194,13 +333,13 @@ impl NodeRunner {
                 }
             }
             ExprAst::Lt { lhs, rhs } => {
-                let mut lhs = self.evaluate_expr(*lhs);
-                let mut rhs = self.evaluate_expr(*rhs);
+                let lhs = self.evaluate_expr(*lhs);
+                let rhs = self.evaluate_expr(*rhs);
 
                 let lhs_type = self.get_value_type(&lhs);
                 let rhs_type = self.get_value_type(&rhs);
 
-                return match (lhs_type, rhs_type) {
+                match (lhs_type, rhs_type) {
                     (1, 1) => { EvalValue::BooleanLiteral { val: lhs.get_int() < rhs.get_int() } }
                     (3, 3) => { EvalValue::BooleanLiteral { val: lhs.get_float() < rhs.get_float() } }
                     (1, 3) => { EvalValue::BooleanLiteral { val: (lhs.get_int() as f32) < rhs.get_float() } }
@@ -211,7 +350,7 @@ impl NodeRunner {
         }
     }
 
-    fn handle_function_call(&mut self, name: String, mut args: Vec<ExprAst>) -> Option<EvalValue> {
+    fn handle_function_call(&mut self, name: String, args: Vec<ExprAst>) -> Option<EvalValue> {
         if ["print", "println", "format"].contains(&name.as_str()) {
             self.call_builtin_function(name, args.into_iter().collect())
         } else {
@@ -222,7 +361,7 @@ impl NodeRunner {
     fn call_builtin_function(&mut self, name: String, mut args: VecDeque<ExprAst>) -> Option<EvalValue> {
         match name.as_str() {
             "print" => {
-                let mut args = args.into_iter().map(|arg| self.evaluate_expr(arg)).collect::<Vec<EvalValue>>();
+                let args = args.into_iter().map(|arg| self.evaluate_expr(arg)).collect::<Vec<EvalValue>>();
                 let mut output = String::new();
                 for arg in args {
                     output.push_str(&arg.to_string());
@@ -233,7 +372,7 @@ impl NodeRunner {
                 None
             },
             "println" => {
-                let mut args = args.into_iter().map(|arg| self.evaluate_expr(arg)).collect::<Vec<EvalValue>>();
+                let args = args.into_iter().map(|arg| self.evaluate_expr(arg)).collect::<Vec<EvalValue>>();
                 let mut output = String::new();
                 for arg in args {
                     output.push_str(&arg.to_string());
@@ -244,33 +383,21 @@ impl NodeRunner {
                 None
             },
             "format" => {
-                if args.len() < 1 {
+                if args.is_empty() {
                     panic!("format function takes 1 or more arguments, {} given", args.len())
                 }
 
-                let mut string = match self.evaluate_expr(args.pop_front().unwrap()) {
+                let template = match self.evaluate_expr(args.pop_front().unwrap()) {
                     EvalValue::Stringliteral { val } => val,
                     _ => panic!("format function takes a string as first argument")
                 };
 
-                while args.len() > 0 {
-                    let replace_with = args.pop_front().unwrap();
-                    match self.evaluate_expr(replace_with) {
-                        EvalValue::IntegerLiteral { val } |
-                        EvalValue::Stringliteral { val } |
-                        EvalValue::FloatLiteral { val } => {
-                            string = string.replacen("{}", &val.to_string(), 1);
-                        }
-                        EvalValue::BooleanLiteral { val } => {
-                            string = string.replacen("{}", &val.to_string(), 1);
-                        }
-                        _ => unreachable!("how?")
-                    }
-                }
-
+                let args = args.into_iter().map(|arg| self.evaluate_expr(arg)).collect::<Vec<EvalValue>>();
+                let string = format_spec::format(&template, &args)
+                    .unwrap_or_else(|error| panic!("{}", ErrorKind::Format(error).message(Language::English)));
                 Some(EvalValue::Stringliteral { val: string })
             }
-            _ => unimplemented!("builtin function {} is not implemented", name)
+            _ => panic!("{}", ErrorKind::FunctionNotFound(name).message(Language::English))
         }
     }
 
@@ -295,12 +422,33 @@ impl NodeRunner {
             Statement::FunctionCall { name, args } => {
                 self.handle_function_call(name, args);
             },
-            Statement::FunctionDefinition { name, args, body } => {
+            Statement::FunctionDefinition { name: _, args: _, body: _ } => {
                 unimplemented!()
             },
             Statement::ForLoop { var_name, start, end, body } => {
-                // when implementing, remember a loop might be before it
-                unimplemented!()
+                let start = self.evaluate_expr(start).get_int();
+                let end = self.evaluate_expr(end).get_int();
+                for i in start..end {
+                    self.locals.insert(var_name.clone(), EvalValue::IntegerLiteral { val: i.to_string() });
+                    if let Some(0) = self.run(body.clone(), true) {
+                        break;
+                    }
+                }
+                self.locals.remove(&var_name);
+            },
+            Statement::ForEachLoop { var_name, iterable, body } => {
+                let items = match self.evaluate_expr(iterable) {
+                    EvalValue::List { val } => val,
+                    _ => unreachable!("can only loop over a list")
+                };
+                for item in items {
+                    let item = self.evaluate_expr(item);
+                    self.locals.insert(var_name.clone(), item);
+                    if let Some(0) = self.run(body.clone(), true) {
+                        break;
+                    }
+                }
+                self.locals.remove(&var_name);
             },
             Statement::Conditional { condition, body, else_if_conditions, else_body } => {
                 let result = match self.evaluate_expr(condition) {
@@ -327,8 +475,8 @@ impl NodeRunner {
                             _ => unreachable!("invalid condition")
                         };
                     }
-                    if !ran && else_body.is_some() {
-                        self.run(else_body.unwrap(), from_loop);
+                    if let (false, Some(else_body)) = (ran, else_body) {
+                        self.run(else_body, from_loop);
                     }
                 }
 
@@ -340,8 +488,7 @@ impl NodeRunner {
                     _ => unreachable!("invalid condition")
                 } {
                     let res = self.run(body.clone(), true);
-                    if res.is_some() {
-                        let res = res.unwrap();
+                    if let Some(res) = res {
                         if res == 0 {
                             break;
                         } else if res == 1 {
@@ -373,7 +520,7 @@ impl NodeRunner {
 
     pub fn run(&mut self, stmts: Block, from_loop: bool) -> Option<u8>{
         self.locals_scope.push(0);
-        for statement in stmts {
+        for (_, statement) in stmts {
             let res = self.execute(statement, from_loop);
             if res.is_some() { return  res }
         }
@@ -383,4 +530,15 @@ impl NodeRunner {
         }
         None
     }
-}
\ No newline at end of file
+}
+
+/// the remainder with the sign of the divisor, so `-7 % 3` is 2
+fn floor_rem(lhs: i32, rhs: i32) -> i32 {
+    let rem = lhs % rhs;
+    if rem != 0 && (rem < 0) != (rhs < 0) { rem + rhs } else { rem }
+}
+
+fn floor_rem_float(lhs: f32, rhs: f32) -> f32 {
+    let rem = lhs % rhs;
+    if rem != 0.0 && (rem < 0.0) != (rhs < 0.0) { rem + rhs } else { rem }
+}
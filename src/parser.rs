@@ -1,13 +1,34 @@
 use std::collections::VecDeque;
 use pest::iterators::{Pair, Pairs};
 use pest::Parser;
+use crate::diagnostics::{Diagnostic, ErrorKind};
 use crate::eval_parser::{ExprAst, parse_expression};
 
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct BareParser;
 
-pub type Block = Vec<Statement>;
+/// where a statement starts in the source file, line and column are 1-based like pest reports them,
+/// offsets are u32 to keep errors small
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub start: u32,
+    pub end: u32,
+    /// which source file, 0 is the one that was run and every imported module gets the next number
+    pub file: u32,
+}
+
+impl Span {
+    pub fn from_pair(pair: &Pair<Rule>) -> Self {
+        let span = pair.as_span();
+        let (line, column) = span.start_pos().line_col();
+        Self { line, column, start: span.start() as u32, end: span.end() as u32, file: 0 }
+    }
+}
+
+pub type Block = Vec<(Span, Statement)>;
 
 #[derive(Debug, Clone)]
 pub enum Statement {
@@ -22,6 +43,12 @@ pub enum Statement {
         name: String,
         value: ExprAst
     },
+    /// xs[i] = value, or xs[i][j] = value with one index per level
+    IndexReassignment {
+        name: String,
+        indices: Vec<ExprAst>,
+        value: ExprAst
+    },
     FunctionCall {
         name: String,
         args: Vec<ExprAst>
@@ -43,56 +70,71 @@ pub enum Statement {
         end: ExprAst,
         body: Block
     },
+    ForEachLoop {
+        var_name: String,
+        iterable: ExprAst,
+        body: Block
+    },
     WhileLoop {
         condition: ExprAst,
         body: Block
     },
+    /// sulayi { body } dakpa name { catch } katapusan { finally }, at least one of catch and finally is there
+    TryCatch {
+        body: Block,
+        catch: Option<(String, Block)>,
+        finally: Option<Block>
+    },
+    Throw { value: ExprAst },
+    /// ipasulod { a, lib.b }, each module name as it was written
+    Import { modules: Vec<String> },
     Break,
     Continue,
     Return { returns: Vec<ExprAst>},
     ClassDeclaration {
         class_name: String,
-        members: Vec<String>
+        members: Vec<String>,
+        /// FunctionDefinitions, each one gets the instance as kani on top of its own args
+        methods: Block,
     }
 }
 
-fn parse_body(pairs: Pairs<Rule>) -> Block {
+fn parse_body(pairs: Pairs<Rule>) -> Result<Block, Diagnostic> {
     let mut statements = Vec::new();
 
     for pair in pairs {
-        match parse_statement(pair) {
-            Some(statement) => statements.push(statement),
-            None => {}
-        }
+        let span = Span::from_pair(&pair);
+        if let Some(statement) = parse_statement(pair)? { statements.push((span, statement)) }
     }
 
-    statements
+    Ok(statements)
 }
 
-fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
-    return match pair.as_rule() {
+fn parse_statement(pair: Pair<Rule>) -> Result<Option<Statement>, Diagnostic> {
+    let span = Span::from_pair(&pair);
+    let statement = match pair.as_rule() {
         Rule::variable_assignment => {
             let mut inner = pair.into_inner();
             let name = inner.next().unwrap().as_str().to_string();
             if name.contains("."){
-               panic!("Variable name cannot contain a period .");
+                return Err(Diagnostic::new(ErrorKind::InvalidVariableName(name), Some(span)));
             }
             let value = parse_expression(inner.next().unwrap());
 
             Some(Statement::VariableAssignment { name, value })
         },
         Rule::variable_multi_assignment => {
-            let mut inner = pair.into_inner();
+            let inner = pair.into_inner();
             let mut variables = vec![];
             for part in inner {
                 if part.as_rule() != Rule::identifier {
                     let value = parse_expression(part);
-                    return Some(Statement::VariableMultiAssignment { variables, value })
+                    return Ok(Some(Statement::VariableMultiAssignment { variables, value }))
                 }
 
                 let name = part.as_str().to_string();
                 if name.contains("."){
-                    panic!("Variable name cannot contain a period .");
+                    return Err(Diagnostic::new(ErrorKind::InvalidVariableName(name), Some(span)));
                 }
                 variables.push(name);
             }
@@ -106,6 +148,16 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
 
             Some(Statement::VariableReassignment { name, value })
         },
+        Rule::index_reassignment => {
+            let mut inner = pair.into_inner().collect::<VecDeque<Pair<Rule>>>();
+            let name = inner.pop_front().unwrap().as_str().to_string();
+            let value = parse_expression(inner.pop_back().expect("Grammar error: no value"));
+            let indices = inner.into_iter()
+                .map(|index| parse_expression(index.into_inner().next().unwrap()))
+                .collect();
+
+            Some(Statement::IndexReassignment { name, indices, value })
+        },
         Rule::function_call_statement => {
             let mut inner = pair.into_inner().next().unwrap().into_inner();
             let name = inner.next().unwrap().as_str().to_string();
@@ -121,7 +173,7 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
             let mut inner = pair.into_inner().collect::<VecDeque<Pair<Rule>>>();
             let mut if_statement = inner.pop_front().unwrap().into_inner().collect::<VecDeque<Pair<Rule>>>();
             let condition = parse_expression(if_statement.pop_front().expect("Grammar error: no condition"));
-            let mut body = parse_body(if_statement.pop_front().unwrap().into_inner());
+            let body = parse_body(if_statement.pop_front().unwrap().into_inner())?;
 
 
             let mut else_if_conditions = vec![];
@@ -132,13 +184,13 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
                     Rule::conditional_else_if => {
                         let mut else_if_statement = statement.into_inner().collect::<VecDeque<Pair<Rule>>>();
                         let condition = parse_expression(else_if_statement.pop_front().expect("Grammar error: no condition"));
-                        let mut body = parse_body(else_if_statement.pop_front().unwrap().into_inner());
+                        let body = parse_body(else_if_statement.pop_front().unwrap().into_inner())?;
 
                         else_if_conditions.push((condition, body));
                     },
                     Rule::conditional_else => {
                         let mut else_statement = statement.into_inner().collect::<VecDeque<Pair<Rule>>>();
-                        let mut body = parse_body(else_statement.pop_front().unwrap().into_inner());
+                        let body = parse_body(else_statement.pop_front().unwrap().into_inner())?;
 
                         else_body = Some(body);
                         break
@@ -152,14 +204,61 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
         Rule::while_loop => {
             let mut inner = pair.into_inner().collect::<VecDeque<Pair<Rule>>>();
             let condition = parse_expression(inner.pop_front().expect("Grammar error: no condition"));
-            let body = parse_body(inner.pop_front().unwrap().into_inner());
+            let body = parse_body(inner.pop_front().unwrap().into_inner())?;
 
             Some(Statement::WhileLoop { condition, body })
         }
+        Rule::for_loop => {
+            let mut inner = pair.into_inner().collect::<VecDeque<Pair<Rule>>>();
+            let var_name = inner.pop_front().unwrap().as_str().to_string();
+            let start = parse_expression(inner.pop_front().expect("Grammar error: no start"));
+            let end = parse_expression(inner.pop_front().expect("Grammar error: no end"));
+            let body = parse_body(inner.pop_front().unwrap().into_inner())?;
+
+            Some(Statement::ForLoop { var_name, start, end, body })
+        }
+        Rule::for_each_loop => {
+            let mut inner = pair.into_inner().collect::<VecDeque<Pair<Rule>>>();
+            let var_name = inner.pop_front().unwrap().as_str().to_string();
+            let iterable = parse_expression(inner.pop_front().expect("Grammar error: no list"));
+            let body = parse_body(inner.pop_front().unwrap().into_inner())?;
+
+            Some(Statement::ForEachLoop { var_name, iterable, body })
+        }
+        Rule::try_catch => {
+            let mut inner = pair.into_inner().collect::<VecDeque<Pair<Rule>>>();
+            let body = parse_body(inner.pop_front().unwrap().into_inner())?;
+            let mut catch = None;
+            let mut finally = None;
+
+            for clause in inner {
+                match clause.as_rule() {
+                    Rule::catch_clause => {
+                        let mut catch_clause = clause.into_inner();
+                        let name = catch_clause.next().unwrap().as_str().to_string();
+                        catch = Some((name, parse_body(catch_clause.next().unwrap().into_inner())?));
+                    }
+                    Rule::finally_clause => {
+                        finally = Some(parse_body(clause.into_inner().next().unwrap().into_inner())?);
+                    }
+                    _ => { unreachable!("not a dakpa or katapusan clause") }
+                }
+            }
+
+            Some(Statement::TryCatch { body, catch, finally })
+        }
+        Rule::import_statement => {
+            let modules = pair.into_inner().map(|module| module.as_str().to_string()).collect();
+            Some(Statement::Import { modules })
+        }
+        Rule::throw_kw => {
+            let value = parse_expression(pair.into_inner().next().expect("Grammar error: nothing to throw"));
+            Some(Statement::Throw { value })
+        }
         Rule::break_kw => Some(Statement::Break),
         Rule::continue_kw => Some(Statement::Continue),
         Rule::return_kw => {
-            let mut inner = pair.into_inner();
+            let inner = pair.into_inner();
             let mut returns = Vec::new();
 
             for expr in inner {
@@ -181,8 +280,8 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
                                 args.push(arg.as_str().to_string());
                             },
                             Rule::block => {
-                                let body = parse_body(arg.into_inner());
-                                return Some(Statement::FunctionDefinition { name, args, body })
+                                let body = parse_body(arg.into_inner())?;
+                                return Ok(Some(Statement::FunctionDefinition { name, args, body }))
                             },
                             _ => { unreachable!("not an identifier or block") }
                         }
@@ -195,26 +294,34 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
             let mut inner = pair.into_inner().collect::<VecDeque<Pair<Rule>>>();
             let class_name = inner.pop_front().unwrap().as_str().to_string();
             let mut members = vec![];
+            let mut methods = vec![];
             for pair in inner {
-                members.push(pair.as_str().to_string())
+                match pair.as_rule() {
+                    Rule::identifier => members.push(pair.as_str().to_string()),
+                    Rule::function_declaration => {
+                        let span = Span::from_pair(&pair);
+                        if let Some(method) = parse_statement(pair)? {
+                            methods.push((span, method))
+                        }
+                    }
+                    Rule::comment => {}
+                    _ => unreachable!("not a member or method: {:?}", pair)
+                }
             }
-            Some(Statement::ClassDeclaration { class_name, members })
+            Some(Statement::ClassDeclaration { class_name, members, methods })
         }
         Rule::comment => {
             // println!("{}", pair.as_str());
             None
         }
         _ => unreachable!("not a statement: {:?}", pair)
-    }
-
+    };
+    Ok(statement)
 }
 
-pub fn parse_file_data(file_data: &str) -> Vec<Statement> {
+pub fn parse_file_data(file_data: &str) -> Result<Block, Diagnostic> {
     let file_data = file_data.to_string() + "\n";
-    let res = match BareParser::parse(Rule::program, &*file_data){
-        Ok(res) => res,
-        Err(e) => panic!("Error: {}", e)
-    };
+    let res = BareParser::parse(Rule::program, &file_data).map_err(Diagnostic::from_pest)?;
     // println!("{:#?}", res);
 
     let mut statements = Vec::new();
@@ -225,15 +332,13 @@ pub fn parse_file_data(file_data: &str) -> Vec<Statement> {
         match pair.as_rule() {
             Rule::EOI => { break }
             _ => {
-                match parse_statement(pair) {
-                    Some(statement) => {
-                        // println!("{:#?}", statement);
-                        statements.push(statement)
-                    },
-                    None => {  }
+                let span = Span::from_pair(&pair);
+                if let Some(statement) = parse_statement(pair)? {
+                    // println!("{:#?}", statement);
+                    statements.push((span, statement))
                 }
             }
         }
     }
-    statements
+    Ok(statements)
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// xoshiro256** seeded through splitmix64, small and good enough for games and shuffling,
+/// the same binhi always gives the same numbers on every platform
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: [u64; 4],
+}
+
+fn splitmix64(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *seed;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        let mut seed = seed;
+        Self { state: [splitmix64(&mut seed), splitmix64(&mut seed), splitmix64(&mut seed), splitmix64(&mut seed)] }
+    }
+
+    /// seeded from the clock, for programs that never call random.binhi
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let shifted = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= shifted;
+        self.state[3] = self.state[3].rotate_left(45);
+        result
+    }
+
+    /// a float in [0, 1) from the top 53 bits
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// a number in [0, bound), rejecting the values that would make the low numbers more likely
+    pub fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound
+            }
+        }
+    }
+
+    /// a number from low to high, both included
+    pub fn between(&mut self, low: i64, high: i64) -> i64 {
+        let span = high.wrapping_sub(low) as u64;
+        if span == u64::MAX {
+            return self.next_u64() as i64
+        }
+        low.wrapping_add(self.below(span + 1) as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_numbers() {
+        let (mut first, mut second) = (Random::new(42), Random::new(42));
+        let numbers: Vec<u64> = (0..5).map(|_| first.next_u64()).collect();
+        assert_eq!(numbers, (0..5).map(|_| second.next_u64()).collect::<Vec<_>>());
+        assert_ne!(numbers[0], Random::new(43).next_u64());
+    }
+
+    #[test]
+    fn stays_in_range() {
+        let mut random = Random::new(7);
+        for _ in 0..1000 {
+            assert!(random.below(6) < 6);
+            assert!((-3..=3).contains(&random.between(-3, 3)));
+            assert!((0.0..1.0).contains(&random.next_f64()));
+        }
+        assert_eq!(random.between(5, 5), 5);
+        random.between(i64::MIN, i64::MAX);
+    }
+
+    #[test]
+    fn reaches_both_ends_of_a_range() {
+        let mut random = Random::new(1);
+        let rolls: Vec<i64> = (0..200).map(|_| random.between(1, 6)).collect();
+        assert!(rolls.contains(&1) && rolls.contains(&6));
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use crate::diagnostics::{Diagnostic, ErrorKind};
+use crate::parser::Span;
+use crate::virtual_machine::{Instruction, IntValue, ObjectCreator, ValueType};
+
+/// where an instruction was reached from, every instruction belongs to the top level or to one proseso
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    TopLevel,
+    /// the location of the proseso's first instruction
+    Function(usize),
+}
+
+/// how many values an instruction pops and pushes, calls are left out because that depends on the argument count before them
+pub(crate) fn stack_effect(instruction: &Instruction) -> (usize, usize) {
+    match instruction {
+        Instruction::Add | Instruction::Sub | Instruction::Mul | Instruction::Div | Instruction::Mod | Instruction::FloorDiv |
+        Instruction::Pow | Instruction::BitAnd | Instruction::BitOr | Instruction::BitXor | Instruction::Shl | Instruction::Shr |
+        Instruction::Eq | Instruction::Neq | Instruction::Lt | Instruction::Gt | Instruction::LtEq | Instruction::GtEq |
+        Instruction::Index => (2, 1),
+        Instruction::Neg | Instruction::BitNot | Instruction::Iterable | Instruction::LoadMember(_) => (1, 1),
+        Instruction::Load(_) | Instruction::Push(_) => (0, 1),
+        Instruction::Store(_) | Instruction::NewVariable(_) | Instruction::JumpIfFalse(_) | Instruction::JumpIfTrue(_) |
+        Instruction::Throw | Instruction::Pop | Instruction::Return => (1, 0),
+        Instruction::Delete(_) | Instruction::Jump(_) | Instruction::TryBegin(_) | Instruction::TryEnd | Instruction::Nop => (0, 0),
+        Instruction::StoreIndex => (3, 0),
+        Instruction::BuildList(length) => (*length, 1),
+        Instruction::BuildMap(length) => (length * 2, 1),
+        Instruction::Unpack(count) => (1, *count),
+        Instruction::Clone => (1, 2),
+        Instruction::Swap => (2, 2),
+        Instruction::Rotate => (3, 3),
+        Instruction::MoveBack(amount) => (amount + 1, amount + 1),
+        Instruction::Call(_) | Instruction::CallMethod(_) => unreachable!("calls depend on their argument count"),
+    }
+}
+
+/// how many values a call pops and pushes, None when the instruction before it doesn't push its argument count
+pub(crate) fn call_effect(call: &Instruction, previous: Option<&Instruction>) -> Option<(usize, usize)> {
+    let count = match previous {
+        Some(Instruction::Push(ValueType::Int(IntValue::Int32(count)))) if *count >= 0 => *count as usize,
+        _ => return None,
+    };
+    // the count and the arguments go, and a method's object goes with them
+    let receiver = usize::from(matches!(call, Instruction::CallMethod(_)));
+    Some((count + 1 + receiver, 1))
+}
+
+/// Checks a compiled program before the virtual machine runs it: every jump lands on an instruction,
+/// the stack never runs out, every place is reached with the same stack depth from every direction,
+/// and the top level ends with an empty stack. Locations count from 1 like the jumps do.
+///
+/// A proseso starts with the arguments and their count on the stack, and its first instruction pushes
+/// the count it expects, so that is where the depth at its entry comes from.
+pub fn verify(instructions: &[Instruction], functions: &HashMap<String, usize>, classes: &HashMap<String, ObjectCreator>, source_map: &[Span]) -> Result<(), Diagnostic> {
+    let end = instructions.len() + 1;
+    let fail = |location: usize, message: String| {
+        let span = location.checked_sub(1).and_then(|index| source_map.get(index)).copied();
+        Err(Diagnostic::new(ErrorKind::InvalidBytecode(format!("instruction {}: {}", location, message)), span))
+    };
+    let instruction_at = |location: usize| location.checked_sub(1).and_then(|index| instructions.get(index));
+
+    let mut entries = vec![(1, 0, Frame::TopLevel)];
+    let methods = classes.values().flat_map(|class| class.methods().map(|(_, location)| *location));
+    for location in functions.values().copied().chain(methods) {
+        match instruction_at(location) {
+            Some(Instruction::Push(ValueType::Int(count))) if count.as_i64() >= 0 => {
+                entries.push((location, count.as_i64() as usize + 1, Frame::Function(location)))
+            }
+            Some(_) => return fail(location, "a proseso has to start by pushing how many arguments it takes".to_string()),
+            None => return fail(location, format!("a proseso starts outside of the program, which ends at {}", end - 1)),
+        }
+    }
+
+    let mut depths: Vec<Option<(usize, Frame)>> = vec![None; end + 1];
+    let mut pending = entries;
+    while let Some((location, depth, frame)) = pending.pop() {
+        if location == 0 || location > end {
+            return fail(location, format!("jumps outside of the program, which ends at {}", end - 1));
+        }
+        match depths[location] {
+            Some((seen, seen_frame)) if seen == depth && seen_frame == frame => continue,
+            Some((_, seen_frame)) if seen_frame != frame => {
+                return fail(location, "is reached from the top level and from a proseso, or from two of them".to_string())
+            }
+            Some((seen, _)) => return fail(location, format!("is reached with {} values on the stack from one place and {} from another", seen, depth)),
+            None => depths[location] = Some((depth, frame)),
+        }
+        let Some(instruction) = instruction_at(location) else {
+            // running off the end finishes the program, which only the top level may do and only with nothing left over
+            match frame {
+                Frame::TopLevel if depth == 0 => continue,
+                Frame::TopLevel => return fail(location, format!("the program ends with {} values left on the stack", depth)),
+                Frame::Function(_) => return fail(location, "a proseso runs off the end of the program without a Return".to_string()),
+            }
+        };
+
+        let (pops, pushes) = match instruction {
+            Instruction::Call(_) | Instruction::CallMethod(_) => match call_effect(instruction, instruction_at(location - 1)) {
+                Some(effect) => effect,
+                None => return fail(location, "a call has to come right after pushing its argument count".to_string()),
+            },
+            instruction => stack_effect(instruction),
+        };
+        if pops > depth {
+            return fail(location, format!("{:?} needs {} values but the stack only has {}", instruction, pops, depth));
+        }
+        let depth = depth - pops + pushes;
+
+        match instruction {
+            Instruction::Return => match frame {
+                Frame::Function(_) if depth == 0 => {}
+                Frame::Function(_) => return fail(location, format!("Return leaves {} values behind besides the one it gives back", depth)),
+                Frame::TopLevel => return fail(location, "Return outside of a proseso".to_string()),
+            },
+            Instruction::Throw => {}
+            Instruction::Jump(target) => pending.push((*target, depth, frame)),
+            Instruction::JumpIfFalse(target) | Instruction::JumpIfTrue(target) => {
+                pending.push((*target, depth, frame));
+                pending.push((location + 1, depth, frame));
+            }
+            Instruction::TryBegin(target) => {
+                // the handler gets the stack as it was here, with the Error object on top
+                pending.push((*target, depth + 1, frame));
+                pending.push((location + 1, depth, frame));
+            }
+            _ => pending.push((location + 1, depth, frame)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::instruction_compiler;
+    use crate::parser::parse_file_data;
+    use super::*;
+
+    fn int(value: i32) -> Instruction {
+        Instruction::Push(ValueType::Int(IntValue::Int32(value)))
+    }
+
+    fn problem(instructions: Vec<Instruction>, functions: &[(&str, usize)]) -> String {
+        let functions = functions.iter().map(|(name, location)| (name.to_string(), *location)).collect();
+        match verify(&instructions, &functions, &HashMap::new(), &[]).expect_err("the program should be rejected").kind {
+            ErrorKind::InvalidBytecode(problem) => problem,
+            kind => panic!("expected InvalidBytecode, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn accepts_compiled_programs() {
+        let source = r#"
+klase Tao {
+    pangalan
+    proseso ngalan() {
+        balik kani.pangalan
+    }
+}
+proseso fib(n) {
+    kon n < 2 {
+        balik n
+    }
+    balik fib(n - 1) + fib(n - 2)
+}
+deklara xs = [Tao("Ana").ngalan(), fib(10)]
+para x sa xs {
+    sulayi {
+        kon x == 55 {
+            buwag
+        }
+        ilabay x
+    } dakpa err {
+        println(err.message)
+    } katapusan {
+        xs[0] = f"{x:>3}"
+    }
+}
+"#;
+        let (instructions, functions, classes, source_map) =
+            instruction_compiler::compile(parse_file_data(source).unwrap(), Path::new("test.bis"), &mut vec![]).unwrap();
+        verify(&instructions, &functions, &classes, &source_map).unwrap();
+    }
+
+    #[test]
+    fn rejects_jumps_outside_the_program() {
+        assert_eq!(problem(vec![Instruction::Jump(5)], &[]), "instruction 5: jumps outside of the program, which ends at 1");
+        assert_eq!(problem(vec![int(1), Instruction::JumpIfTrue(0)], &[]), "instruction 0: jumps outside of the program, which ends at 2");
+    }
+
+    #[test]
+    fn rejects_unbalanced_stack_depths() {
+        // the condition's false branch reaches the Pop with nothing on the stack, the true branch with one value
+        let instructions = vec![int(1), Instruction::JumpIfFalse(4), int(1), Instruction::Pop];
+        assert_eq!(problem(instructions, &[]), "instruction 4: is reached with 1 values on the stack from one place and 0 from another");
+        assert_eq!(problem(vec![int(1)], &[]), "instruction 2: the program ends with 1 values left on the stack");
+        assert_eq!(problem(vec![int(1), Instruction::Add], &[]), "instruction 2: Add needs 2 values but the stack only has 1");
+    }
+
+    #[test]
+    fn rejects_bad_calls_and_returns() {
+        assert_eq!(problem(vec![int(1), Instruction::Return], &[]), "instruction 2: Return outside of a proseso");
+        assert_eq!(problem(vec![Instruction::Call("f".to_string())], &[]), "instruction 1: a call has to come right after pushing its argument count");
+        assert_eq!(problem(vec![Instruction::Nop], &[("f", 1)]), "instruction 1: a proseso has to start by pushing how many arguments it takes");
+        assert_eq!(problem(vec![Instruction::Nop], &[("f", 3)]), "instruction 3: a proseso starts outside of the program, which ends at 1");
+        // f takes no arguments, so it starts with the count on the stack and pops it before running off the end
+        let instructions = vec![Instruction::Jump(4), int(0), Instruction::Pop];
+        assert_eq!(problem(instructions, &[("f", 2)]), "instruction 4: a proseso runs off the end of the program without a Return");
+    }
+}
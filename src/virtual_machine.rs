@@ -1,8 +1,25 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::collections::vec_deque::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Signed, ToPrimitive};
+use crate::diagnostics::{ErrorKind, Language};
+use crate::format_spec::{self, FormatArgument};
+use crate::garbage_collector::Heap;
+use crate::parser::Span;
+use crate::random::Random;
 
 
-const BUILTIN_FUNCTIONS: [&str; 8] = [
+/// the class of the objects dakpa gets, with the message and line of the error
+const ERROR_CLASS: &str = "Error";
+
+/// modules that ipasulod finds in here instead of looking for a .bis file, their proseso are builtins named module.proseso
+pub const NATIVE_MODULES: [&str; 1] = ["random"];
+
+const BUILTIN_FUNCTIONS: [&str; 18] = [
     "print",
     "println",
     "format",
@@ -10,7 +27,17 @@ const BUILTIN_FUNCTIONS: [&str; 8] = [
     "push",
     "pop",
     "read_element",
-    "write_element"
+    "write_element",
+    "length",
+    "gc_stats",
+    "keys",
+    "values",
+    "naa",
+    "random.binhi",
+    "random.numero",
+    "random.decimal",
+    "random.pilian",
+    "random.shuffle"
 ];
 
 
@@ -20,12 +47,15 @@ pub enum IntValue {
     Int16(i16),
     Int32(i32),
     Int64(i64),
+    /// where Int64 promotes to when it overflows, it never holds a value that fits in an Int64
+    BigInt(BigInt),
 }
 
-#[derive(Clone, Debug)]
-enum CharValue {
-    Char(char),
-}
+/// a BigInt counts as wider than every fixed width
+const BIG_INT_SIZE: usize = 16;
+
+/// ints never overflow, Int64 promotes to a BigInt, but *, ** and << stop here instead of eating all the memory
+const MAX_INT_BITS: u64 = 1 << 22;
 
 #[derive(Clone, Debug)]
 pub struct  StringValue {
@@ -43,6 +73,25 @@ pub enum FloatValue {
     Float64(f64),
 }
 
+/// a map key, ints of every width are the same key
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Int(i64),
+    BigInt(BigInt),
+    Bool(bool),
+    String(String),
+}
+
+/// keeps entries in the order they were first inserted, so printing and looping are predictable
+#[derive(Clone, Debug, Default)]
+pub struct MapValue {
+    entries: Vec<(MapKey, Value)>,
+    positions: HashMap<MapKey, usize>,
+}
+
+/// Ints, floats, bools and strings are values, cloning one gives an independent copy.
+/// Lists and maps are references, cloning one gives another handle to the same list, so a list
+/// passed to a proseso or stored in two variables is changed for everyone holding it.
 #[derive(Clone, Debug)]
 pub enum ValueType {
     Int(IntValue),
@@ -50,39 +99,164 @@ pub enum ValueType {
     Bool(BoolValue),
     // Char(Char),
     String(StringValue),
-    Vector(Vec<Value>),
+    Vector(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<MapValue>>),
+    /// nothing, what a proseso without balik gives back
+    Wala,
 }
 
+/// class instances are always references, like lists
 #[derive(Clone, Debug)]
 pub enum Value {
-    Object(Object),
+    Object(Rc<RefCell<Object>>),
     Value(ValueType)
 }
 
+fn member_of(value: &Value, member: &str) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Object(obj) => obj.borrow().get_member(member),
+        Value::Value(val) => Err(RuntimeError::from_kind(ErrorKind::NoMembers(val.type_name())))
+    }
+}
+
+impl MapKey {
+    fn new(key: &ValueType) -> Result<Self, RuntimeError> {
+        match key {
+            ValueType::Int(IntValue::BigInt(big)) => Ok(MapKey::BigInt(big.clone())),
+            ValueType::Int(int) => Ok(MapKey::Int(int.as_i64())),
+            ValueType::Bool(bool) => Ok(MapKey::Bool(bool.value)),
+            ValueType::String(string) => Ok(MapKey::String(string.value.clone())),
+            val => Err(RuntimeError::from_kind(ErrorKind::InvalidMapKey(val.type_name())))
+        }
+    }
+    fn to_value(&self) -> Value {
+        Value::Value(match self {
+            MapKey::Int(int) => ValueType::Int(IntValue::Int64(*int)),
+            MapKey::BigInt(big) => ValueType::Int(IntValue::BigInt(big.clone())),
+            MapKey::Bool(bool) => ValueType::Bool(BoolValue::new(*bool)),
+            MapKey::String(string) => ValueType::String(StringValue::new(string.clone())),
+        })
+    }
+}
+
+impl fmt::Display for MapKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapKey::Int(int) => write!(f, "{}", int),
+            MapKey::BigInt(big) => write!(f, "{}", big),
+            MapKey::Bool(bool) => write!(f, "{}", bool),
+            MapKey::String(string) => write!(f, "{}", string),
+        }
+    }
+}
+
+impl MapValue {
+    pub fn get(&self, key: &MapKey) -> Option<&Value> {
+        self.positions.get(key).map(|&position| &self.entries[position].1)
+    }
+    pub fn insert(&mut self, key: MapKey, value: Value) {
+        match self.positions.get(&key) {
+            Some(&position) => self.entries[position].1 = value,
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+    pub fn contains(&self, key: &MapKey) -> bool {
+        self.positions.contains_key(key)
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &MapKey> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.positions.clear();
+    }
+}
+
+/// turns an index into a position in a list of the given length, negative indices count from the end
+fn list_index(index: &ValueType, length: usize) -> Result<usize, RuntimeError> {
+    let index = match index {
+        ValueType::Int(int) => int.as_i64(),
+        val => return Err(RuntimeError::from_kind(ErrorKind::InvalidIndex(val.type_name())))
+    };
+    let position = if index < 0 { index + length as i64 } else { index };
+    if position < 0 || position >= length as i64 {
+        return Err(RuntimeError::from_kind(ErrorKind::IndexOutOfRange { index, length }));
+    }
+    Ok(position as usize)
+}
+
 impl Value {
-    fn to_val(&self) -> ValueType {
+    fn to_val(&self) -> Result<ValueType, RuntimeError> {
         match self {
-            Value::Object(s) => s.get_value().unwrap().clone(),
-            Value::Value(v) => v.clone()
+            Value::Object(s) => Err(RuntimeError::from_kind(ErrorKind::ObjectAsValue(s.borrow().name.clone()))),
+            Value::Value(v) => Ok(v.clone())
         }
     }
     pub fn as_string(&self) -> String {
+        self.as_string_inside(&mut vec![])
+    }
+    /// `parents` are the lists and objects being printed around this value, a value that contains
+    /// itself prints as [...] or Name {...} instead of recursing forever
+    fn as_string_inside(&self, parents: &mut Vec<*const ()>) -> String {
         match self {
             Value::Object(s) => {
-                match s.get_value() {
-                    Some(v) => v.as_string(),
-                    None => {
-                        format!("{:?} {:?}", s.name, s.values)
-                    }
+                let id = Rc::as_ptr(s) as *const ();
+                let s = s.borrow();
+                if parents.contains(&id) {
+                    return format!("{} {{...}}", s.name);
                 }
+                parents.push(id);
+                let mut members = s.values.iter().collect::<Vec<_>>();
+                members.sort_by(|lhs, rhs| lhs.0.cmp(rhs.0));
+                let members = members.into_iter()
+                    .map(|(name, value)| format!("{}: {}", name, value.as_string_inside(parents)))
+                    .collect::<Vec<String>>();
+                parents.pop();
+                format!("{} {{ {} }}", s.name, members.join(", "))
             },
+            Value::Value(ValueType::Vector(vec)) => {
+                let id = Rc::as_ptr(vec) as *const ();
+                if parents.contains(&id) {
+                    return "[...]".to_string();
+                }
+                parents.push(id);
+                let items = vec.borrow().iter().map(|value| value.as_string_inside(parents)).collect::<Vec<String>>();
+                parents.pop();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Value(ValueType::Map(map)) => {
+                let id = Rc::as_ptr(map) as *const ();
+                if parents.contains(&id) {
+                    return "{...}".to_string();
+                }
+                parents.push(id);
+                let entries = map.borrow().entries.iter()
+                    .map(|(key, value)| format!("{}: {}", key, value.as_string_inside(parents)))
+                    .collect::<Vec<String>>();
+                parents.pop();
+                format!("{{{}}}", entries.join(", "))
+            }
             Value::Value(v) => v.as_string()
         }
     }
-    pub fn from_int_val(val: IntValue) -> Self {
-        Self::Value(ValueType::Int(val))
+    pub fn new_object(object: Object) -> Self {
+        Self::Object(Rc::new(RefCell::new(object)))
+    }
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Object(_) => "object",
+            Value::Value(val) => val.type_name()
+        }
     }
-
     pub fn from_bool(val: bool) -> Self {
         Self::Value(ValueType::Bool(BoolValue::new(val)))
     }
@@ -99,6 +273,9 @@ impl BoolValue {
     pub fn new(value: bool) -> Self {
         BoolValue { value }
     }
+    pub fn value(&self) -> bool {
+        self.value
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -107,6 +284,16 @@ pub enum Instruction {
     Sub,
     Mul,
     Div,
+    Mod,
+    FloorDiv,
+    Pow,
+    Neg,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    BitNot,
     Eq,
     Neq,
     Lt,
@@ -121,6 +308,28 @@ pub enum Instruction {
     JumpIfFalse(usize),
     JumpIfTrue(usize),
     Call(String),
+    /// calls a method on the object sitting under the arguments, the object becomes `kani`
+    CallMethod(String),
+    /// pops an index and a list, pushes the element
+    Index,
+    /// pops an object and pushes its member with that name
+    LoadMember(String),
+    /// pops a value, an index and a list, and writes the value into that list
+    StoreIndex,
+    /// pops that many values into a new list, the first one pushed becomes the first element
+    BuildList(usize),
+    /// pops that many key and value pairs into a new map, each key is pushed before its value
+    BuildMap(usize),
+    /// replaces the value on top with the list a para loop walks over, maps give a list of their keys
+    Iterable,
+    /// pops a list of exactly that many items and pushes them last to first, so the next NewVariable gets the first one
+    Unpack(usize),
+    /// from here until the matching TryEnd, a runtime error jumps to the handler with an Error object on the stack
+    TryBegin(usize),
+    /// leaves the innermost sulayi block without an error
+    TryEnd,
+    /// pops a value and raises it, an Error object is raised as is and anything else becomes its message
+    Throw,
     Push(ValueType),
     Pop,
     Nop,
@@ -132,15 +341,97 @@ pub enum Instruction {
 }
 
 
+/// A single `proseso` activation, locals live here until the matching `Return`
+#[derive(Debug)]
+pub struct Frame {
+    function_name: String,
+    return_address: usize,
+    locals: HashMap<String, Value>,
+    /// how deep the operand stack was under the arguments, the proseso leaves exactly one value above it
+    stack_base: usize,
+}
+
+/// a sulayi block that is still running, a runtime error unwinds the vm back to how it was when the block started
+#[derive(Debug)]
+struct Handler {
+    address: usize,
+    stack_len: usize,
+    frames: usize,
+    /// the variables of the scope the block started in, anything declared after that is dropped when an error is caught
+    variables: HashSet<String>,
+}
+
 #[derive(Debug)]
 pub struct VirtualMachine {
     stack: Vec<Value>,
     rom: Vec<Instruction>,
     pc: usize,
-    heap: HashMap<String, Object>,
+    globals: HashMap<String, Value>,
+    /// every list and object allocated while running, for the garbage collector
+    heap: Heap,
     functions: HashMap<String, usize>,
-    call_stack: Vec<usize>,
+    call_stack: Vec<Frame>,
     class_definitions: HashMap<String, ObjectCreator>,
+    source_map: Vec<Span>,
+    handlers: Vec<Handler>,
+    /// the Error object an ilabay is raising again, so dakpa gets the original back
+    thrown: Option<Rc<RefCell<Object>>>,
+    /// the language caught errors get their message in
+    language: Language,
+    /// the generator behind the random module, seeded from the clock until random.binhi is called
+    random: Random,
+    /// checks that every statement leaves the stack the way it found it, for --debug
+    debug: bool,
+}
+
+/// an error raised while running a program, instead of taking the whole interpreter down with a panic
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    /// the statement that was running when the error happened
+    pub span: Option<Span>,
+    /// the proseso calls that were active, outermost first, with the span of each call site
+    pub trace: Vec<(String, Option<Span>)>,
+}
+
+impl RuntimeError {
+    pub fn from_kind(kind: ErrorKind) -> Self {
+        Self { kind, span: None, trace: vec![] }
+    }
+    pub fn invalid_operation(operation: &'static str, lhs: &'static str, rhs: &'static str) -> Self {
+        Self::from_kind(ErrorKind::InvalidOperation { operation, lhs, rhs })
+    }
+    pub fn argument_count(function: &str, min: usize, max: Option<usize>, given: usize) -> Self {
+        Self::from_kind(ErrorKind::ArgumentCount { function: function.into(), min, max, given })
+    }
+    pub fn argument_type(function: &str, expected: &'static [&'static str], found: &'static str) -> Self {
+        Self::from_kind(ErrorKind::ArgumentType { function: function.into(), expected, found })
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "Runtime error at line {}, column {}: {}", span.line, span.column, self.kind.message(Language::English))?,
+            None => write!(f, "Runtime error: {}", self.kind.message(Language::English))?
+        }
+        for (function_name, call_site) in self.trace.iter().rev() {
+            match call_site {
+                Some(span) => write!(f, "\n    in proseso {} called at line {}, column {}", function_name, span.line, span.column)?,
+                None => write!(f, "\n    in proseso {}", function_name)?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for IntValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntValue::BigInt(big) => write!(f, "{}", big),
+            int => write!(f, "{}", int.as_i64()),
+        }
+    }
 }
 
 impl IntValue {
@@ -150,6 +441,7 @@ impl IntValue {
             IntValue::Int16(i) => *i as i8,
             IntValue::Int32(i) => *i as i8,
             IntValue::Int64(i) => *i as i8,
+            IntValue::BigInt(_) => self.as_i64() as i8,
         }
     }
     pub fn as_i16(&self) -> i16 {
@@ -158,6 +450,7 @@ impl IntValue {
             IntValue::Int16(i) => *i,
             IntValue::Int32(i) => *i as i16,
             IntValue::Int64(i) => *i as i16,
+            IntValue::BigInt(_) => self.as_i64() as i16,
         }
     }
     pub fn as_i32(&self) -> i32 {
@@ -166,6 +459,7 @@ impl IntValue {
             IntValue::Int16(i) => *i as i32,
             IntValue::Int32(i) => *i,
             IntValue::Int64(i) => *i as i32,
+            IntValue::BigInt(_) => self.as_i64() as i32,
         }
     }
     pub fn as_i64(&self) -> i64 {
@@ -174,6 +468,33 @@ impl IntValue {
             IntValue::Int16(i) => *i as i64,
             IntValue::Int32(i) => *i as i64,
             IntValue::Int64(i) => *i,
+            // saturates, a BigInt is always out of range
+            IntValue::BigInt(i) => if i.is_negative() { i64::MIN } else { i64::MAX },
+        }
+    }
+    pub fn as_big(&self) -> BigInt {
+        match self {
+            IntValue::BigInt(i) => i.clone(),
+            _ => BigInt::from(self.as_i64()),
+        }
+    }
+    /// goes back down to an Int64 when the value fits again
+    pub fn from_big(big: BigInt) -> IntValue {
+        match big.to_i64() {
+            Some(i) => IntValue::Int64(i),
+            None => IntValue::BigInt(big),
+        }
+    }
+    pub fn as_f32(&self) -> f32 {
+        match self {
+            IntValue::BigInt(i) => i.to_f32().unwrap_or(f32::NAN),
+            _ => self.as_i64() as f32,
+        }
+    }
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            IntValue::BigInt(i) => i.to_f64().unwrap_or(f64::NAN),
+            _ => self.as_i64() as f64,
         }
     }
     pub fn get_byte_size(&self) -> usize {
@@ -182,6 +503,7 @@ impl IntValue {
             IntValue::Int16(_) => 2,
             IntValue::Int32(_) => 4,
             IntValue::Int64(_) => 8,
+            IntValue::BigInt(_) => BIG_INT_SIZE,
         }
     }
     pub fn use_size_of_biggest(&self, other: &Self) -> usize {
@@ -212,15 +534,19 @@ impl IntValue {
     pub fn add_64(lhs: i64, rhs: i64) -> IntValue {
         match lhs.checked_add(rhs) {
             Some(r) => IntValue::Int64(r),
-            None => { panic!("Integer overflow") }
+            None => { Self::add_big(BigInt::from(lhs), BigInt::from(rhs)) }
         }
     }
+    pub fn add_big(lhs: BigInt, rhs: BigInt) -> IntValue {
+        Self::from_big(lhs + rhs)
+    }
     pub fn add(self, other: Self) -> Self {
         match &self.use_size_of_biggest(&other) {
             1 => Self::add_8(self.as_i8(), other.as_i8()),
             2 => Self::add_16(self.as_i16(), other.as_i16()),
             4 => Self::add_32(self.as_i32(), other.as_i32()),
             8 => Self::add_64(self.as_i64(), other.as_i64()),
+            &BIG_INT_SIZE => Self::add_big(self.as_big(), other.as_big()),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -245,15 +571,19 @@ impl IntValue {
     pub fn sub_64(lhs: i64, rhs: i64) -> IntValue {
         match lhs.checked_sub(rhs) {
             Some(r) => IntValue::Int64(r),
-            None => { panic!("Integer overflow") }
+            None => { Self::sub_big(BigInt::from(lhs), BigInt::from(rhs)) }
         }
     }
+    pub fn sub_big(lhs: BigInt, rhs: BigInt) -> IntValue {
+        Self::from_big(lhs - rhs)
+    }
     pub fn sub(self, other: Self) -> Self {
         match &self.use_size_of_biggest(&other) {
             1 => Self::sub_8(self.as_i8(), other.as_i8()),
             2 => Self::sub_16(self.as_i16(), other.as_i16()),
             4 => Self::sub_32(self.as_i32(), other.as_i32()),
             8 => Self::sub_64(self.as_i64(), other.as_i64()),
+            &BIG_INT_SIZE => Self::sub_big(self.as_big(), other.as_big()),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -278,15 +608,19 @@ impl IntValue {
     pub fn mul_64(lhs: i64, rhs: i64) -> IntValue {
         match lhs.checked_mul(rhs) {
             Some(r) => IntValue::Int64(r),
-            None => { panic!("Integer overflow") }
+            None => { Self::mul_big(BigInt::from(lhs), BigInt::from(rhs)) }
         }
     }
+    pub fn mul_big(lhs: BigInt, rhs: BigInt) -> IntValue {
+        Self::from_big(lhs * rhs)
+    }
     pub fn mul(self, other: Self) -> Self {
         match &self.use_size_of_biggest(&other) {
             1 => Self::mul_8(self.as_i8(), other.as_i8()),
             2 => Self::mul_16(self.as_i16(), other.as_i16()),
             4 => Self::mul_32(self.as_i32(), other.as_i32()),
             8 => Self::mul_64(self.as_i64(), other.as_i64()),
+            &BIG_INT_SIZE => Self::mul_big(self.as_big(), other.as_big()),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -311,24 +645,288 @@ impl IntValue {
     pub fn div_64(lhs: i64, rhs: i64) -> IntValue {
         match lhs.checked_div(rhs) {
             Some(r) => IntValue::Int64(r),
-            None => { panic!("Integer overflow") }
+            None => { Self::div_big(BigInt::from(lhs), BigInt::from(rhs)) }
         }
     }
+    pub fn div_big(lhs: BigInt, rhs: BigInt) -> IntValue {
+        Self::from_big(lhs / rhs)
+    }
     pub fn div(self, other: Self) -> Self {
         match &self.use_size_of_biggest(&other) {
             1 => Self::div_8(self.as_i8(), other.as_i8()),
             2 => Self::div_16(self.as_i16(), other.as_i16()),
             4 => Self::div_32(self.as_i32(), other.as_i32()),
             8 => Self::div_64(self.as_i64(), other.as_i64()),
+            &BIG_INT_SIZE => Self::div_big(self.as_big(), other.as_big()),
+            _ => panic!("Invalid byte size for integer value"),
+        }
+    }
+    pub fn rem_8(lhs: i8, rhs: i8) -> IntValue {
+        match lhs.checked_rem(rhs) {
+            Some(r) if r != 0 && (r < 0) != (rhs < 0) => IntValue::Int8(r + rhs),
+            Some(r) => IntValue::Int8(r),
+            None => { Self::rem_16(lhs as i16, rhs as i16) }
+        }
+    }
+    pub fn rem_16(lhs: i16, rhs: i16) -> IntValue {
+        match lhs.checked_rem(rhs) {
+            Some(r) if r != 0 && (r < 0) != (rhs < 0) => IntValue::Int16(r + rhs),
+            Some(r) => IntValue::Int16(r),
+            None => { Self::rem_32(lhs as i32, rhs as i32) }
+        }
+    }
+    pub fn rem_32(lhs: i32, rhs: i32) -> IntValue {
+        match lhs.checked_rem(rhs) {
+            Some(r) if r != 0 && (r < 0) != (rhs < 0) => IntValue::Int32(r + rhs),
+            Some(r) => IntValue::Int32(r),
+            None => { Self::rem_64(lhs as i64, rhs as i64) }
+        }
+    }
+    pub fn rem_64(lhs: i64, rhs: i64) -> IntValue {
+        match lhs.checked_rem(rhs) {
+            Some(r) if r != 0 && (r < 0) != (rhs < 0) => IntValue::Int64(r + rhs),
+            Some(r) => IntValue::Int64(r),
+            None => { Self::rem_big(BigInt::from(lhs), BigInt::from(rhs)) }
+        }
+    }
+    pub fn rem_big(lhs: BigInt, rhs: BigInt) -> IntValue {
+        Self::from_big(lhs.mod_floor(&rhs))
+    }
+    /// the remainder takes the sign of the divisor, so a == (a // b) * b + a % b
+    pub fn rem(self, other: Self) -> Self {
+        match &self.use_size_of_biggest(&other) {
+            1 => Self::rem_8(self.as_i8(), other.as_i8()),
+            2 => Self::rem_16(self.as_i16(), other.as_i16()),
+            4 => Self::rem_32(self.as_i32(), other.as_i32()),
+            8 => Self::rem_64(self.as_i64(), other.as_i64()),
+            &BIG_INT_SIZE => Self::rem_big(self.as_big(), other.as_big()),
+            _ => panic!("Invalid byte size for integer value"),
+        }
+    }
+    pub fn floor_div_8(lhs: i8, rhs: i8) -> IntValue {
+        match (lhs.checked_div(rhs), lhs.checked_rem(rhs)) {
+            (Some(q), Some(r)) if r != 0 && (r < 0) != (rhs < 0) => IntValue::Int8(q - 1),
+            (Some(q), _) => IntValue::Int8(q),
+            _ => { Self::floor_div_16(lhs as i16, rhs as i16) }
+        }
+    }
+    pub fn floor_div_16(lhs: i16, rhs: i16) -> IntValue {
+        match (lhs.checked_div(rhs), lhs.checked_rem(rhs)) {
+            (Some(q), Some(r)) if r != 0 && (r < 0) != (rhs < 0) => IntValue::Int16(q - 1),
+            (Some(q), _) => IntValue::Int16(q),
+            _ => { Self::floor_div_32(lhs as i32, rhs as i32) }
+        }
+    }
+    pub fn floor_div_32(lhs: i32, rhs: i32) -> IntValue {
+        match (lhs.checked_div(rhs), lhs.checked_rem(rhs)) {
+            (Some(q), Some(r)) if r != 0 && (r < 0) != (rhs < 0) => IntValue::Int32(q - 1),
+            (Some(q), _) => IntValue::Int32(q),
+            _ => { Self::floor_div_64(lhs as i64, rhs as i64) }
+        }
+    }
+    pub fn floor_div_64(lhs: i64, rhs: i64) -> IntValue {
+        match (lhs.checked_div(rhs), lhs.checked_rem(rhs)) {
+            (Some(q), Some(r)) if r != 0 && (r < 0) != (rhs < 0) => IntValue::Int64(q - 1),
+            (Some(q), _) => IntValue::Int64(q),
+            _ => { Self::floor_div_big(BigInt::from(lhs), BigInt::from(rhs)) }
+        }
+    }
+    pub fn floor_div_big(lhs: BigInt, rhs: BigInt) -> IntValue {
+        Self::from_big(lhs.div_floor(&rhs))
+    }
+    /// rounds towards negative infinity, unlike div which rounds towards zero
+    pub fn floor_div(self, other: Self) -> Self {
+        match &self.use_size_of_biggest(&other) {
+            1 => Self::floor_div_8(self.as_i8(), other.as_i8()),
+            2 => Self::floor_div_16(self.as_i16(), other.as_i16()),
+            4 => Self::floor_div_32(self.as_i32(), other.as_i32()),
+            8 => Self::floor_div_64(self.as_i64(), other.as_i64()),
+            &BIG_INT_SIZE => Self::floor_div_big(self.as_big(), other.as_big()),
+            _ => panic!("Invalid byte size for integer value"),
+        }
+    }
+    pub fn pow_8(lhs: i8, exponent: u32) -> IntValue {
+        match lhs.checked_pow(exponent) {
+            Some(r) => IntValue::Int8(r),
+            None => { Self::pow_16(lhs as i16, exponent) }
+        }
+    }
+    pub fn pow_16(lhs: i16, exponent: u32) -> IntValue {
+        match lhs.checked_pow(exponent) {
+            Some(r) => IntValue::Int16(r),
+            None => { Self::pow_32(lhs as i32, exponent) }
+        }
+    }
+    pub fn pow_32(lhs: i32, exponent: u32) -> IntValue {
+        match lhs.checked_pow(exponent) {
+            Some(r) => IntValue::Int32(r),
+            None => { Self::pow_64(lhs as i64, exponent) }
+        }
+    }
+    pub fn pow_64(lhs: i64, exponent: u32) -> IntValue {
+        match lhs.checked_pow(exponent) {
+            Some(r) => IntValue::Int64(r),
+            None => { Self::pow_big(BigInt::from(lhs), exponent) }
+        }
+    }
+    pub fn pow_big(lhs: BigInt, exponent: u32) -> IntValue {
+        Self::from_big(lhs.pow(exponent))
+    }
+    /// starts at the width of the base, the exponent's width doesn't matter
+    pub fn pow(self, exponent: u32) -> Self {
+        match self.get_byte_size() {
+            1 => Self::pow_8(self.as_i8(), exponent),
+            2 => Self::pow_16(self.as_i16(), exponent),
+            4 => Self::pow_32(self.as_i32(), exponent),
+            8 => Self::pow_64(self.as_i64(), exponent),
+            BIG_INT_SIZE => Self::pow_big(self.as_big(), exponent),
+            _ => panic!("Invalid byte size for integer value"),
+        }
+    }
+    pub fn neg_8(value: i8) -> IntValue {
+        match value.checked_neg() {
+            Some(r) => IntValue::Int8(r),
+            None => { Self::neg_16(value as i16) }
+        }
+    }
+    pub fn neg_16(value: i16) -> IntValue {
+        match value.checked_neg() {
+            Some(r) => IntValue::Int16(r),
+            None => { Self::neg_32(value as i32) }
+        }
+    }
+    pub fn neg_32(value: i32) -> IntValue {
+        match value.checked_neg() {
+            Some(r) => IntValue::Int32(r),
+            None => { Self::neg_64(value as i64) }
+        }
+    }
+    pub fn neg_64(value: i64) -> IntValue {
+        match value.checked_neg() {
+            Some(r) => IntValue::Int64(r),
+            None => { Self::neg_big(BigInt::from(value)) }
+        }
+    }
+    pub fn neg_big(value: BigInt) -> IntValue {
+        Self::from_big(-value)
+    }
+    pub fn neg(self) -> Self {
+        match self.get_byte_size() {
+            1 => Self::neg_8(self.as_i8()),
+            2 => Self::neg_16(self.as_i16()),
+            4 => Self::neg_32(self.as_i32()),
+            8 => Self::neg_64(self.as_i64()),
+            BIG_INT_SIZE => Self::neg_big(self.as_big()),
+            _ => panic!("Invalid byte size for integer value"),
+        }
+    }
+    pub fn is_zero(&self) -> bool {
+        self.as_i64() == 0
+    }
+    /// how many bits the magnitude needs, without the sign
+    pub fn bits(&self) -> u64 {
+        match self {
+            IntValue::BigInt(int) => int.bits(),
+            _ => (u64::BITS - self.as_i64().unsigned_abs().leading_zeros()) as u64,
+        }
+    }
+    /// & | and ^ work on the two's complement bits at the width of the wider operand,
+    /// the narrower one is sign extended first, so the result never needs promoting
+    pub fn bit_and(self, other: Self) -> Self {
+        match &self.use_size_of_biggest(&other) {
+            1 => IntValue::Int8(self.as_i8() & other.as_i8()),
+            2 => IntValue::Int16(self.as_i16() & other.as_i16()),
+            4 => IntValue::Int32(self.as_i32() & other.as_i32()),
+            8 => IntValue::Int64(self.as_i64() & other.as_i64()),
+            &BIG_INT_SIZE => Self::from_big(self.as_big() & other.as_big()),
+            _ => panic!("Invalid byte size for integer value"),
+        }
+    }
+    pub fn bit_or(self, other: Self) -> Self {
+        match &self.use_size_of_biggest(&other) {
+            1 => IntValue::Int8(self.as_i8() | other.as_i8()),
+            2 => IntValue::Int16(self.as_i16() | other.as_i16()),
+            4 => IntValue::Int32(self.as_i32() | other.as_i32()),
+            8 => IntValue::Int64(self.as_i64() | other.as_i64()),
+            &BIG_INT_SIZE => Self::from_big(self.as_big() | other.as_big()),
+            _ => panic!("Invalid byte size for integer value"),
+        }
+    }
+    pub fn bit_xor(self, other: Self) -> Self {
+        match &self.use_size_of_biggest(&other) {
+            1 => IntValue::Int8(self.as_i8() ^ other.as_i8()),
+            2 => IntValue::Int16(self.as_i16() ^ other.as_i16()),
+            4 => IntValue::Int32(self.as_i32() ^ other.as_i32()),
+            8 => IntValue::Int64(self.as_i64() ^ other.as_i64()),
+            &BIG_INT_SIZE => Self::from_big(self.as_big() ^ other.as_big()),
+            _ => panic!("Invalid byte size for integer value"),
+        }
+    }
+    /// ~x == -x - 1 at any width, so it keeps the width of x
+    pub fn bit_not(self) -> Self {
+        match self {
+            IntValue::Int8(i) => IntValue::Int8(!i),
+            IntValue::Int16(i) => IntValue::Int16(!i),
+            IntValue::Int32(i) => IntValue::Int32(!i),
+            IntValue::Int64(i) => IntValue::Int64(!i),
+            IntValue::BigInt(i) => Self::from_big(!i),
+        }
+    }
+    pub fn shl_8(lhs: i8, amount: u32) -> IntValue {
+        if amount < i8::BITS && (lhs << amount) >> amount == lhs {
+            return IntValue::Int8(lhs << amount);
+        }
+        Self::shl_16(lhs as i16, amount)
+    }
+    pub fn shl_16(lhs: i16, amount: u32) -> IntValue {
+        if amount < i16::BITS && (lhs << amount) >> amount == lhs {
+            return IntValue::Int16(lhs << amount);
+        }
+        Self::shl_32(lhs as i32, amount)
+    }
+    pub fn shl_32(lhs: i32, amount: u32) -> IntValue {
+        if amount < i32::BITS && (lhs << amount) >> amount == lhs {
+            return IntValue::Int32(lhs << amount);
+        }
+        Self::shl_64(lhs as i64, amount)
+    }
+    pub fn shl_64(lhs: i64, amount: u32) -> IntValue {
+        if amount < i64::BITS && (lhs << amount) >> amount == lhs {
+            return IntValue::Int64(lhs << amount);
+        }
+        Self::shl_big(BigInt::from(lhs), amount)
+    }
+    pub fn shl_big(lhs: BigInt, amount: u32) -> IntValue {
+        Self::from_big(lhs << amount)
+    }
+    /// x << n is x * 2 ** n, so bits shifted past the width promote it instead of being lost
+    pub fn shl(self, amount: u32) -> Self {
+        match self.get_byte_size() {
+            1 => Self::shl_8(self.as_i8(), amount),
+            2 => Self::shl_16(self.as_i16(), amount),
+            4 => Self::shl_32(self.as_i32(), amount),
+            8 => Self::shl_64(self.as_i64(), amount),
+            BIG_INT_SIZE => Self::shl_big(self.as_big(), amount),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
+    /// an arithmetic shift, x >> n is x // 2 ** n, and shifting by the width or more leaves 0 or -1
+    pub fn shr(self, amount: u32) -> Self {
+        match self {
+            IntValue::Int8(i) => IntValue::Int8(i >> amount.min(i8::BITS - 1)),
+            IntValue::Int16(i) => IntValue::Int16(i >> amount.min(i16::BITS - 1)),
+            IntValue::Int32(i) => IntValue::Int32(i >> amount.min(i32::BITS - 1)),
+            IntValue::Int64(i) => IntValue::Int64(i >> amount.min(i64::BITS - 1)),
+            IntValue::BigInt(i) => Self::from_big(i >> amount),
+        }
+    }
     pub fn eq(self, other: Self) -> bool {
         match &self.use_size_of_biggest(&other) {
             1 => self.as_i8() == other.as_i8(),
             2 => self.as_i16() == other.as_i16(),
             4 => self.as_i32() == other.as_i32(),
             8 => self.as_i64() == other.as_i64(),
+            &BIG_INT_SIZE => self.as_big() == other.as_big(),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -338,6 +936,7 @@ impl IntValue {
             2 => self.as_i16() != other.as_i16(),
             4 => self.as_i32() != other.as_i32(),
             8 => self.as_i64() != other.as_i64(),
+            &BIG_INT_SIZE => self.as_big() != other.as_big(),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -347,6 +946,7 @@ impl IntValue {
             2 => self.as_i16() < other.as_i16(),
             4 => self.as_i32() < other.as_i32(),
             8 => self.as_i64() < other.as_i64(),
+            &BIG_INT_SIZE => self.as_big() < other.as_big(),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -356,6 +956,7 @@ impl IntValue {
             2 => self.as_i16() <= other.as_i16(),
             4 => self.as_i32() <= other.as_i32(),
             8 => self.as_i64() <= other.as_i64(),
+            &BIG_INT_SIZE => self.as_big() <= other.as_big(),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -365,6 +966,7 @@ impl IntValue {
             2 => self.as_i16() > other.as_i16(),
             4 => self.as_i32() > other.as_i32(),
             8 => self.as_i64() > other.as_i64(),
+            &BIG_INT_SIZE => self.as_big() > other.as_big(),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -374,6 +976,7 @@ impl IntValue {
             2 => self.as_i16() >= other.as_i16(),
             4 => self.as_i32() >= other.as_i32(),
             8 => self.as_i64() >= other.as_i64(),
+            &BIG_INT_SIZE => self.as_big() >= other.as_big(),
             _ => panic!("Invalid byte size for integer value"),
         }
     }
@@ -478,6 +1081,77 @@ impl FloatValue {
             _ => panic!("Invalid byte size for float value"),
         }
     }
+    pub fn rem_32(lhs: f32, rhs: f32) -> FloatValue {
+        let mut res = lhs % rhs;
+        if res != 0.0 && (res < 0.0) != (rhs < 0.0) {
+            res += rhs;
+        }
+        FloatValue::Float32(res)
+    }
+    pub fn rem_64(lhs: f64, rhs: f64) -> FloatValue {
+        let mut res = lhs % rhs;
+        if res != 0.0 && (res < 0.0) != (rhs < 0.0) {
+            res += rhs;
+        }
+        FloatValue::Float64(res)
+    }
+    /// the remainder takes the sign of the divisor, like for ints
+    pub fn rem(self, other: Self) -> Self {
+        match &self.use_size_of_biggest(&other) {
+            4 => Self::rem_32(self.as_f32(), other.as_f32()),
+            8 => Self::rem_64(self.as_f64(), other.as_f64()),
+            _ => panic!("Invalid byte size for float value"),
+        }
+    }
+    pub fn floor_div_32(lhs: f32, rhs: f32) -> FloatValue {
+        let res = (lhs / rhs).floor();
+        if res.is_finite() {
+            FloatValue::Float32(res)
+        } else {
+            Self::floor_div_64(lhs as f64, rhs as f64)
+        }
+    }
+    pub fn floor_div_64(lhs: f64, rhs: f64) -> FloatValue {
+        FloatValue::Float64((lhs / rhs).floor())
+    }
+    pub fn floor_div(self, other: Self) -> Self {
+        match &self.use_size_of_biggest(&other) {
+            4 => Self::floor_div_32(self.as_f32(), other.as_f32()),
+            8 => Self::floor_div_64(self.as_f64(), other.as_f64()),
+            _ => panic!("Invalid byte size for float value"),
+        }
+    }
+    pub fn pow_32(lhs: f32, rhs: f32) -> FloatValue {
+        let res = lhs.powf(rhs);
+        if res.is_finite() {
+            FloatValue::Float32(res)
+        } else {
+            Self::pow_64(lhs as f64, rhs as f64)
+        }
+    }
+    pub fn pow_64(lhs: f64, rhs: f64) -> FloatValue {
+        FloatValue::Float64(lhs.powf(rhs))
+    }
+    pub fn pow(self, other: Self) -> Self {
+        match &self.use_size_of_biggest(&other) {
+            4 => Self::pow_32(self.as_f32(), other.as_f32()),
+            8 => Self::pow_64(self.as_f64(), other.as_f64()),
+            _ => panic!("Invalid byte size for float value"),
+        }
+    }
+    pub fn neg(self) -> Self {
+        match self {
+            FloatValue::Float32(f) => FloatValue::Float32(-f),
+            FloatValue::Float64(f) => FloatValue::Float64(-f),
+        }
+    }
+    /// an int as a float of the same width as `like`, for mixing ints and floats in one operation
+    pub fn from_int_like(int: &IntValue, like: &FloatValue) -> Self {
+        match like {
+            FloatValue::Float32(_) => FloatValue::Float32(int.as_f32()),
+            FloatValue::Float64(_) => FloatValue::Float64(int.as_f64()),
+        }
+    }
     pub fn eq(self, other: Self) -> bool {
         match &self.use_size_of_biggest(&other) {
             4 => self.as_f32() == other.as_f32(),
@@ -526,30 +1200,75 @@ impl StringValue {
     pub fn new(value: String) -> Self {
         Self { value }
     }
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
     pub fn add(self, other: Self) -> Self {
-        match (self, other) {
-            (StringValue { value: lhs}, StringValue { value: rhs }) => {
-                StringValue::new(lhs + &rhs)
-            }
-        }
+        StringValue::new(self.value + &other.value)
     }
     pub fn eq(self, other: Self) -> bool {
-        match (self, other) {
-            (StringValue { value: lhs}, StringValue { value: rhs }) => lhs == rhs,
-        }
+        self.value == other.value
     }
     pub fn ne(self, other: Self) -> bool {
-        match (self, other) {
-            (StringValue { value: lhs}, StringValue { value: rhs }) => lhs != rhs,
+        self.value != other.value
+    }
+    /// every position below counts characters, not bytes, so "ñ" is one long like it looks
+    pub fn length(&self) -> usize {
+        self.value.chars().count()
+    }
+    pub fn chars(&self) -> impl Iterator<Item = StringValue> + '_ {
+        self.value.chars().map(|c| StringValue::new(c.to_string()))
+    }
+    pub fn char_at(&self, index: &ValueType) -> Result<StringValue, RuntimeError> {
+        let position = list_index(index, self.length())?;
+        Ok(StringValue::new(self.value.chars().nth(position).unwrap().to_string()))
+    }
+    /// where needle first shows up, or -1 when it doesn't
+    pub fn find(&self, needle: &str) -> i64 {
+        match self.value.find(needle) {
+            Some(byte) => self.value[..byte].chars().count() as i64,
+            None => -1,
+        }
+    }
+    /// the characters from start up to but not including end, negative positions count from the back
+    /// and positions past either end are clamped instead of being an error
+    pub fn slice(&self, start: i64, end: i64) -> StringValue {
+        let length = self.length() as i64;
+        let clamp = |position: i64| if position < 0 { (position + length).max(0) } else { position.min(length) };
+        let (start, end) = (clamp(start), clamp(end));
+        StringValue::new(self.value.chars().skip(start as usize).take((end - start).max(0) as usize).collect())
+    }
+    /// with no separator it splits on runs of whitespace and drops the empty ends
+    pub fn split(&self, separator: Option<&str>) -> Vec<StringValue> {
+        match separator {
+            Some(separator) => self.value.split(separator).map(|part| StringValue::new(part.to_string())).collect(),
+            None => self.value.split_whitespace().map(|part| StringValue::new(part.to_string())).collect(),
         }
     }
 }
 
 impl ValueType {
+    pub fn new_list(items: Vec<Value>) -> Self {
+        ValueType::Vector(Rc::new(RefCell::new(items)))
+    }
+    pub fn new_map(map: MapValue) -> Self {
+        ValueType::Map(Rc::new(RefCell::new(map)))
+    }
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ValueType::Int(_) => "int",
+            ValueType::Float(_) => "float",
+            ValueType::Bool(_) => "bool",
+            ValueType::String(_) => "string",
+            ValueType::Vector(_) => "list",
+            ValueType::Map(_) => "map",
+            ValueType::Wala => "wala",
+        }
+    }
     pub fn as_string(&self) -> String {
         match self {
             ValueType::Int(int) => {
-                int.as_i64().to_string()
+                int.to_string()
             }
             ValueType::Float(float) => {
                 float.as_f64().to_string()
@@ -560,21 +1279,29 @@ impl ValueType {
             ValueType::String(string) => {
                 string.value.clone()
             }
-            ValueType::Vector(vec) => {
-                let mut str = "[".to_string();
-                for value in vec {
-                    str += &*value.as_string();
-                    str += ", "
-                }
-                str.pop();
-                str.pop();
-                str += "]";
-                str
+            ValueType::Vector(_) | ValueType::Map(_) => {
+                Value::Value(self.clone()).as_string()
+            }
+            ValueType::Wala => {
+                "wala".to_string()
             }
         }
     }
-    pub fn add(self, other: Self) -> Self {
-        match (self.clone(), other.clone()) {
+    /// a BigInt never fits in an f32, so a Float32 next to one is widened to a Float64 before mixing them
+    pub fn widen_beside_big_int(lhs: Self, rhs: Self) -> (Self, Self) {
+        match (lhs, rhs) {
+            (lhs @ ValueType::Int(IntValue::BigInt(_)), ValueType::Float(FloatValue::Float32(rhs))) => {
+                (lhs, ValueType::Float(FloatValue::Float64(rhs as f64)))
+            }
+            (ValueType::Float(FloatValue::Float32(lhs)), rhs @ ValueType::Int(IntValue::BigInt(_))) => {
+                (ValueType::Float(FloatValue::Float64(lhs as f64)), rhs)
+            }
+            operands => operands,
+        }
+    }
+    pub fn add(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
                 ValueType::Int(lhs_int.add(rhs_int))
             }
@@ -584,38 +1311,38 @@ impl ValueType {
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Float(FloatValue::Float32(lhs_int.as_i64() as f32 + rhs_float))
+                        ValueType::Float(FloatValue::Float32(lhs_int.as_f32() + rhs_float))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Float(FloatValue::Float64(lhs_int.as_i64() as f64  + rhs_float))
+                        ValueType::Float(FloatValue::Float64(lhs_int.as_f64()  + rhs_float))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Float(FloatValue::Float32(lhs_float + rhs_int.as_i64() as f32))
+                        ValueType::Float(FloatValue::Float32(lhs_float + rhs_int.as_f32()))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Float(FloatValue::Float64(lhs_float + rhs_int.as_i64() as f64))
+                        ValueType::Float(FloatValue::Float64(lhs_float + rhs_int.as_f64()))
                     }
                 }
             }
             (ValueType::String(lhs_string), ValueType::String(rhs_string)) => {
                 ValueType::String(lhs_string.add(rhs_string))
             }
-            (ValueType::Vector(vec), ValueType::Vector(mut other_vec)) => {
-                let mut new_vec = vec.clone();
-                new_vec.append(&mut other_vec);
-                ValueType::Vector(new_vec)
+            (ValueType::Vector(vec), ValueType::Vector(other_vec)) => {
+                // concatenating makes a new list, neither side is changed
+                ValueType::new_list(vec.borrow().iter().chain(other_vec.borrow().iter()).cloned().collect())
             }
             _ => {
-                panic!("Invalid types for add operation: {:?} + {:?}", self, other);
+                return Err(RuntimeError::invalid_operation("add", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn sub(self, other: Self) -> Self {
-        match (self, other) {
+    pub fn sub(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
                 ValueType::Int(lhs_int.sub(rhs_int))
             }
@@ -625,31 +1352,37 @@ impl ValueType {
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Float(FloatValue::Float32(lhs_int.as_i64() as f32 - rhs_float))
+                        ValueType::Float(FloatValue::Float32(lhs_int.as_f32() - rhs_float))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Float(FloatValue::Float64(lhs_int.as_i64() as f64 - rhs_float))
+                        ValueType::Float(FloatValue::Float64(lhs_int.as_f64() - rhs_float))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Float(FloatValue::Float32(lhs_float - rhs_int.as_i64() as f32))
+                        ValueType::Float(FloatValue::Float32(lhs_float - rhs_int.as_f32()))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Float(FloatValue::Float64(lhs_float - rhs_int.as_i64() as f64))
+                        ValueType::Float(FloatValue::Float64(lhs_float - rhs_int.as_f64()))
                     }
                 }
             }
             _ => {
-                panic!("Invalid types for sub operation");
+                return Err(RuntimeError::invalid_operation("sub", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn mul(self, other: Self) -> Self {
-        match (self, other) {
+    pub fn mul(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
+                // the product has at least (bits - 1) + (bits - 1) bits, the same bound pow uses,
+                // so squaring in a loop stops here instead of eating all the memory
+                if lhs_int.bits().saturating_sub(1) + rhs_int.bits().saturating_sub(1) > MAX_INT_BITS {
+                    return Err(RuntimeError::from_kind(ErrorKind::IntegerTooBig));
+                }
                 ValueType::Int(lhs_int.mul(rhs_int))
             }
             (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
@@ -658,30 +1391,35 @@ impl ValueType {
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Float(FloatValue::Float32(lhs_int.as_i64() as f32 * rhs_float))
+                        ValueType::Float(FloatValue::Float32(lhs_int.as_f32() * rhs_float))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Float(FloatValue::Float64(lhs_int.as_i64() as f64 * rhs_float))
+                        ValueType::Float(FloatValue::Float64(lhs_int.as_f64() * rhs_float))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Float(FloatValue::Float32(lhs_float * rhs_int.as_i64() as f32))
+                        ValueType::Float(FloatValue::Float32(lhs_float * rhs_int.as_f32()))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Float(FloatValue::Float64(lhs_float * rhs_int.as_i64() as f64))
+                        ValueType::Float(FloatValue::Float64(lhs_float * rhs_int.as_f64()))
                     }
                 }
             }
             _ => {
-                panic!("Invalid types for mul operation");
+                return Err(RuntimeError::invalid_operation("mul", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn div(self, other: Self) -> Self {
-        match (self, other) {
+    /// ints truncate and raise on a zero divisor, anything with a float follows IEEE, so 1.0 / 0 is inf
+    pub fn div(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
+            (ValueType::Int(_), ValueType::Int(rhs_int)) if rhs_int.is_zero() => {
+                return Err(RuntimeError::from_kind(ErrorKind::DivisionByZero));
+            }
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
                 ValueType::Int(lhs_int.div(rhs_int))
             }
@@ -691,294 +1429,425 @@ impl ValueType {
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Float(FloatValue::Float32(lhs_int.as_i64() as f32 / rhs_float))
+                        ValueType::Float(FloatValue::Float32(lhs_int.as_f32() / rhs_float))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Float(FloatValue::Float64(lhs_int.as_i64() as f64 / rhs_float))
+                        ValueType::Float(FloatValue::Float64(lhs_int.as_f64() / rhs_float))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Float(FloatValue::Float32(lhs_float / rhs_int.as_i64() as f32))
+                        ValueType::Float(FloatValue::Float32(lhs_float / rhs_int.as_f32()))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Float(FloatValue::Float64(lhs_float / rhs_int.as_i64() as f64))
+                        ValueType::Float(FloatValue::Float64(lhs_float / rhs_int.as_f64()))
                     }
                 }
             }
             _ => {
-                panic!("Invalid types for div operation");
+                return Err(RuntimeError::invalid_operation("div", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn eq(self, other: Self) -> Self {
-        match (self, other) {
+    /// % and // on ints round towards negative infinity, and like / both of them raise on a zero divisor
+    pub fn rem(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
+            (ValueType::Int(_), ValueType::Int(rhs_int)) if rhs_int.is_zero() => {
+                return Err(RuntimeError::from_kind(ErrorKind::DivisionByZero));
+            }
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
-                ValueType::Bool(BoolValue::new((lhs_int.eq(rhs_int))))
+                ValueType::Int(lhs_int.rem(rhs_int))
             }
             (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
-                ValueType::Bool(BoolValue::new((lhs_float.eq(rhs_float))))
+                ValueType::Float(lhs_float.rem(rhs_float))
             }
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
-                match rhs_float {
-                    FloatValue::Float32(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f32 == rhs_float)))
+                ValueType::Float(FloatValue::from_int_like(&lhs_int, &rhs_float).rem(rhs_float))
+            }
+            (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
+                let rhs_float = FloatValue::from_int_like(&rhs_int, &lhs_float);
+                ValueType::Float(lhs_float.rem(rhs_float))
+            }
+            _ => {
+                return Err(RuntimeError::invalid_operation("mod", lhs_type, rhs_type));
+            }
+        })
+    }
+    pub fn floor_div(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
+            (ValueType::Int(_), ValueType::Int(rhs_int)) if rhs_int.is_zero() => {
+                return Err(RuntimeError::from_kind(ErrorKind::DivisionByZero));
+            }
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
+                ValueType::Int(lhs_int.floor_div(rhs_int))
+            }
+            (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
+                ValueType::Float(lhs_float.floor_div(rhs_float))
+            }
+            (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
+                ValueType::Float(FloatValue::from_int_like(&lhs_int, &rhs_float).floor_div(rhs_float))
+            }
+            (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
+                let rhs_float = FloatValue::from_int_like(&rhs_int, &lhs_float);
+                ValueType::Float(lhs_float.floor_div(rhs_float))
+            }
+            _ => {
+                return Err(RuntimeError::invalid_operation("floordiv", lhs_type, rhs_type));
+            }
+        })
+    }
+    /// an int to a negative int power gives a float, like 2 ** -1 == 0.5
+    pub fn pow(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) if rhs_int.as_i64() < 0 => {
+                ValueType::Float(FloatValue::Float64(lhs_int.as_f64().powf(rhs_int.as_f64())))
+            }
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
+                // exponents past u32 overflow anyway unless the base is 0, 1 or -1, where only the parity matters
+                let exponent = u32::try_from(rhs_int.as_i64()).unwrap_or(u32::MAX - 1 + rhs_int.as_big().is_odd() as u32);
+                // |x| >= 2 ** (bits - 1), so this is a lower bound on the size of the result
+                if lhs_int.bits().saturating_sub(1) * exponent as u64 > MAX_INT_BITS {
+                    return Err(RuntimeError::from_kind(ErrorKind::IntegerTooBig));
+                }
+                ValueType::Int(lhs_int.pow(exponent))
+            }
+            (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
+                ValueType::Float(lhs_float.pow(rhs_float))
+            }
+            (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
+                ValueType::Float(FloatValue::from_int_like(&lhs_int, &rhs_float).pow(rhs_float))
+            }
+            (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
+                let rhs_float = FloatValue::from_int_like(&rhs_int, &lhs_float);
+                ValueType::Float(lhs_float.pow(rhs_float))
+            }
+            _ => {
+                return Err(RuntimeError::invalid_operation("pow", lhs_type, rhs_type));
+            }
+        })
+    }
+    pub fn neg(self) -> Result<Self, RuntimeError> {
+        match self {
+            ValueType::Int(int) => Ok(ValueType::Int(int.neg())),
+            ValueType::Float(float) => Ok(ValueType::Float(float.neg())),
+            val => Err(RuntimeError::from_kind(ErrorKind::InvalidUnaryOperation { operation: "neg", value: val.type_name() }))
+        }
+    }
+    pub fn bit_and(self, other: Self) -> Result<Self, RuntimeError> {
+        match (self, other) {
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => Ok(ValueType::Int(lhs_int.bit_and(rhs_int))),
+            (lhs, rhs) => Err(RuntimeError::invalid_operation("bitand", lhs.type_name(), rhs.type_name())),
+        }
+    }
+    pub fn bit_or(self, other: Self) -> Result<Self, RuntimeError> {
+        match (self, other) {
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => Ok(ValueType::Int(lhs_int.bit_or(rhs_int))),
+            (lhs, rhs) => Err(RuntimeError::invalid_operation("bitor", lhs.type_name(), rhs.type_name())),
+        }
+    }
+    pub fn bit_xor(self, other: Self) -> Result<Self, RuntimeError> {
+        match (self, other) {
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => Ok(ValueType::Int(lhs_int.bit_xor(rhs_int))),
+            (lhs, rhs) => Err(RuntimeError::invalid_operation("bitxor", lhs.type_name(), rhs.type_name())),
+        }
+    }
+    fn shift_amount(amount: &IntValue) -> Result<u32, RuntimeError> {
+        match amount.as_i64() {
+            i if i < 0 => Err(RuntimeError::from_kind(ErrorKind::NegativeShift)),
+            // anything past u32 shifts every bit out anyway
+            i => Ok(u32::try_from(i).unwrap_or(u32::MAX)),
+        }
+    }
+    pub fn shl(self, other: Self) -> Result<Self, RuntimeError> {
+        match (self, other) {
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
+                let amount = Self::shift_amount(&rhs_int)?;
+                if !lhs_int.is_zero() && amount as u64 > MAX_INT_BITS {
+                    return Err(RuntimeError::from_kind(ErrorKind::IntegerTooBig));
+                }
+                Ok(ValueType::Int(lhs_int.shl(amount)))
+            }
+            (lhs, rhs) => Err(RuntimeError::invalid_operation("shl", lhs.type_name(), rhs.type_name())),
+        }
+    }
+    pub fn shr(self, other: Self) -> Result<Self, RuntimeError> {
+        match (self, other) {
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => Ok(ValueType::Int(lhs_int.shr(Self::shift_amount(&rhs_int)?))),
+            (lhs, rhs) => Err(RuntimeError::invalid_operation("shr", lhs.type_name(), rhs.type_name())),
+        }
+    }
+    pub fn bit_not(self) -> Result<Self, RuntimeError> {
+        match self {
+            ValueType::Int(int) => Ok(ValueType::Int(int.bit_not())),
+            val => Err(RuntimeError::from_kind(ErrorKind::InvalidUnaryOperation { operation: "bitnot", value: val.type_name() }))
+        }
+    }
+    pub fn eq(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
+            (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
+                ValueType::Bool(BoolValue::new(lhs_int.eq(rhs_int) ))
+            }
+            (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
+                ValueType::Bool(BoolValue::new(lhs_float.eq(rhs_float) ))
+            }
+            (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
+                match rhs_float {
+                    FloatValue::Float32(rhs_float) => {
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f32() == rhs_float ))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f64 == rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f64() == rhs_float ))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float == rhs_int.as_i64() as f32)))
+                        ValueType::Bool(BoolValue::new(lhs_float == rhs_int.as_f32() ))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float == rhs_int.as_i64() as f64)))
+                        ValueType::Bool(BoolValue::new(lhs_float == rhs_int.as_f64() ))
                     }
                 }
             }
             (ValueType::String(lhs_string), ValueType::String(rhs_string)) => {
-                ValueType::Bool(BoolValue::new((lhs_string.eq(rhs_string))))
+                ValueType::Bool(BoolValue::new(lhs_string.eq(rhs_string) ))
+            }
+            (ValueType::Bool(lhs_bool), ValueType::Bool(rhs_bool)) => {
+                ValueType::Bool(BoolValue::new(lhs_bool.value == rhs_bool.value))
             }
             _ => {
-                panic!("Invalid types for eq operation");
+                return Err(RuntimeError::invalid_operation("eq", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn ne(self, other: Self) -> Self {
-        match (self, other) {
+    pub fn ne(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
-                ValueType::Bool(BoolValue::new((lhs_int.ne(rhs_int))))
+                ValueType::Bool(BoolValue::new(lhs_int.ne(rhs_int) ))
             }
             (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
-                ValueType::Bool(BoolValue::new((lhs_float.ne(rhs_float))))
+                ValueType::Bool(BoolValue::new(lhs_float.ne(rhs_float) ))
             }
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f32 != rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f32() != rhs_float ))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f64 != rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f64() != rhs_float ))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float != rhs_int.as_i64() as f32)))
+                        ValueType::Bool(BoolValue::new(lhs_float != rhs_int.as_f32() ))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float != rhs_int.as_i64() as f64)))
+                        ValueType::Bool(BoolValue::new(lhs_float != rhs_int.as_f64() ))
                     }
                 }
             }
             (ValueType::String(lhs_string), ValueType::String(rhs_string)) => {
-                ValueType::Bool(BoolValue::new((lhs_string.ne(rhs_string))))
+                ValueType::Bool(BoolValue::new(lhs_string.ne(rhs_string) ))
+            }
+            (ValueType::Bool(lhs_bool), ValueType::Bool(rhs_bool)) => {
+                ValueType::Bool(BoolValue::new(lhs_bool.value != rhs_bool.value))
             }
             _ => {
-                panic!("Invalid types for neq operation");
+                return Err(RuntimeError::invalid_operation("neq", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn gt(self, other: Self) -> Self {
-        match (self, other) {
+    pub fn gt(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
-                ValueType::Bool(BoolValue::new((lhs_int.gt(rhs_int))))
+                ValueType::Bool(BoolValue::new(lhs_int.gt(rhs_int) ))
             }
             (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
-                ValueType::Bool(BoolValue::new((lhs_float.gt(rhs_float))))
+                ValueType::Bool(BoolValue::new(lhs_float.gt(rhs_float) ))
             }
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f32 > rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f32() > rhs_float ))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f64 > rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f64() > rhs_float ))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float > rhs_int.as_i64() as f32)))
+                        ValueType::Bool(BoolValue::new(lhs_float > rhs_int.as_f32() ))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float > rhs_int.as_i64() as f64)))
+                        ValueType::Bool(BoolValue::new(lhs_float > rhs_int.as_f64() ))
                     }
                 }
             }
             _ => {
-                panic!("Invalid types for gt operation");
+                return Err(RuntimeError::invalid_operation("gt", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn lt(self, other: Self) -> Self {
-        match (self, other) {
+    pub fn lt(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
-                ValueType::Bool(BoolValue::new((lhs_int.lt(rhs_int))))
+                ValueType::Bool(BoolValue::new(lhs_int.lt(rhs_int) ))
             }
             (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
-                ValueType::Bool(BoolValue::new((lhs_float.lt(rhs_float))))
+                ValueType::Bool(BoolValue::new(lhs_float.lt(rhs_float) ))
             }
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Bool(BoolValue::new(((lhs_int.as_i64() as f32).lt(&rhs_float))))
+                        ValueType::Bool(BoolValue::new((lhs_int.as_f32()).lt(&rhs_float) ))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Bool(BoolValue::new(((lhs_int.as_i64() as f64).lt(&rhs_float))))
+                        ValueType::Bool(BoolValue::new((lhs_int.as_f64()).lt(&rhs_float) ))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float < rhs_int.as_i64() as f32)))
+                        ValueType::Bool(BoolValue::new(lhs_float < rhs_int.as_f32() ))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float < rhs_int.as_i64() as f64)))
+                        ValueType::Bool(BoolValue::new(lhs_float < rhs_int.as_f64() ))
                     }
                 }
             }
             _ => {
-                panic!("Invalid types for lt operation");
+                return Err(RuntimeError::invalid_operation("lt", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn ge(self, other: Self) -> Self {
-        match (self, other) {
+    pub fn ge(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
-                ValueType::Bool(BoolValue::new((lhs_int.ge(rhs_int))))
+                ValueType::Bool(BoolValue::new(lhs_int.ge(rhs_int) ))
             }
             (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
-                ValueType::Bool(BoolValue::new((lhs_float.ge(rhs_float))))
+                ValueType::Bool(BoolValue::new(lhs_float.ge(rhs_float) ))
             }
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f32 >= rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f32() >= rhs_float ))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f64 >= rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f64() >= rhs_float ))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float >= rhs_int.as_i64() as f32)))
+                        ValueType::Bool(BoolValue::new(lhs_float >= rhs_int.as_f32() ))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float >= rhs_int.as_i64() as f64)))
+                        ValueType::Bool(BoolValue::new(lhs_float >= rhs_int.as_f64() ))
                     }
                 }
             }
             _ => {
-                panic!("Invalid types for gte operation");
+                return Err(RuntimeError::invalid_operation("gte", lhs_type, rhs_type));
             }
-        }
+        })
     }
-    pub fn le(self, other: Self) -> Self {
-        match (self, other) {
+    pub fn le(self, other: Self) -> Result<Self, RuntimeError> {
+        let (lhs_type, rhs_type) = (self.type_name(), other.type_name());
+        Ok(match (self, other) {
             (ValueType::Int(lhs_int), ValueType::Int(rhs_int)) => {
-                ValueType::Bool(BoolValue::new((lhs_int.le(rhs_int))))
+                ValueType::Bool(BoolValue::new(lhs_int.le(rhs_int) ))
             }
             (ValueType::Float(lhs_float), ValueType::Float(rhs_float)) => {
-                ValueType::Bool(BoolValue::new((lhs_float.le(rhs_float))))
+                ValueType::Bool(BoolValue::new(lhs_float.le(rhs_float) ))
             }
             (ValueType::Int(lhs_int), ValueType::Float(rhs_float)) => {
                 match rhs_float {
                     FloatValue::Float32(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f32 <= rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f32() <= rhs_float ))
                     }
                     FloatValue::Float64(rhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_int.as_i64() as f64 <= rhs_float)))
+                        ValueType::Bool(BoolValue::new(lhs_int.as_f64() <= rhs_float ))
                     }
                 }
             }
             (ValueType::Float(lhs_float), ValueType::Int(rhs_int)) => {
                 match lhs_float {
                     FloatValue::Float32(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float <= rhs_int.as_i64() as f32)))
+                        ValueType::Bool(BoolValue::new(lhs_float <= rhs_int.as_f32() ))
                     }
                     FloatValue::Float64(lhs_float) => {
-                        ValueType::Bool(BoolValue::new((lhs_float <= rhs_int.as_i64() as f64)))
+                        ValueType::Bool(BoolValue::new(lhs_float <= rhs_int.as_f64() ))
                     }
                 }
             }
             _ => {
-                panic!("Invalid types for lte operation");
+                return Err(RuntimeError::invalid_operation("lte", lhs_type, rhs_type));
             }
+        })
+    }
+}
+
+impl FormatArgument for Value {
+    fn display(&self) -> String {
+        self.as_string()
+    }
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Value(ValueType::Int(int)) => Some(int.as_f64()),
+            Value::Value(ValueType::Float(float)) => Some(float.as_f64()),
+            _ => None,
+        }
+    }
+    fn as_integer(&self) -> Option<BigInt> {
+        match self {
+            Value::Value(ValueType::Int(int)) => Some(int.as_big()),
+            _ => None,
+        }
+    }
+    fn named(&self, name: &str) -> Option<Self> {
+        match self {
+            Value::Value(ValueType::Map(map)) => map.borrow().get(&MapKey::String(name.to_string())).cloned(),
+            Value::Object(object) => object.borrow().get_member(name).ok(),
+            _ => None,
         }
     }
 }
 
 impl Object {
     pub fn new(name: String) -> Self {
-        let mut values = HashMap::new();
+        let values = HashMap::new();
         Self { name, values }
     }
-    fn get_value(&self) -> Option<ValueType> {
-        match self.values.get("__value__") {
-            None => { None }
-            Some(val) => { Some(val.to_val()) }
-        }
-    }
-    // fn get_member(&self, name: String) -> ValueType {
-    //     self.values.get(&name).unwrap().clone().to_val()
-    // }
-    fn set_value(&mut self, val: Value) {
-        self.values.insert("__value__".to_string(), val);
+    fn get_member(&self, name: &str) -> Result<Value, RuntimeError> {
+        self.values.get(name).cloned()
+            .ok_or_else(|| RuntimeError::from_kind(ErrorKind::MemberNotFound { class: self.name.clone(), member: name.to_string() }))
     }
     fn set_member(&mut self, name: String, val: Value) {
-        if name.contains("."){
-            // splits the name into the object name and the member name, then sets the member, member name can have multiple dots
-            let mut split = name.split(".");
-            let obj_name = split.next().unwrap().to_string();
-            let member_name = split.collect::<Vec<&str>>().join(".");
-            match self.values.get_mut(&obj_name).unwrap() {
-                Value::Object(obj) => {
-                    obj.set_member(member_name, val);
-                }
-                _ => {
-                    panic!("Invalid member type");
-                }
-            };
-        } else {
-            self.values.insert(name, val);
-        }
+        self.values.insert(name, val);
     }
-    fn get_member(&mut self, name: String) -> ValueType {
-        if name.contains("."){
-            // splits the name into the object name and the member name, then sets the member, member name can have multiple dots
-            let mut split = name.split(".");
-            let obj_name = split.next().unwrap().to_string();
-            let member_name = split.collect::<Vec<&str>>().join(".");
-            match self.values.get_mut(&obj_name).unwrap(){
-                Value::Object(obj) => {
-                    return obj.get_member(member_name);
-                }
-                _ => {
-                    panic!("Invalid member type");
-                }
-            };
-        } else {
-            return match self.values.get(&name){
-                None => {
-                    println!("{:#?}", self.values);
-                    panic!("Invalid member name, '{}'", name);
-                }
-                Some(val) => {
-                    val.to_val()
-                }
-            }
-        }
+    pub fn members(&self) -> impl Iterator<Item = &Value> {
+        self.values.values()
+    }
+    pub fn clear_members(&mut self) {
+        self.values.clear();
     }
 }
 
@@ -986,43 +1855,100 @@ impl Object {
 pub struct ObjectCreator {
     name: String,
     members: Vec<String>,
+    /// method name to the location of its first instruction
+    methods: HashMap<String, usize>,
 }
 
 impl ObjectCreator {
     pub fn new(name: String) -> Self {
-        Self { name, members: Vec::new() }
+        Self { name, members: Vec::new(), methods: HashMap::new() }
     }
     pub fn add_member(&mut self, name: String) {
         self.members.push(name);
     }
+    pub fn add_method(&mut self, name: String, location: usize) {
+        self.methods.insert(name, location);
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+    pub fn methods(&self) -> impl Iterator<Item = (&String, &usize)> {
+        self.methods.iter()
+    }
+    pub fn get_method(&self, name: &str) -> Option<usize> {
+        self.methods.get(name).copied()
+    }
     pub fn create(&self, members: Vec<Value>) -> Option<Object> {
         if members.len() != self.members.len() {
             return None;
         }
         let mut s = Object::new(self.name.clone());
-        for i in 0..members.len() {
-            s.set_member(self.members[i].clone(), members[i].clone());
+        for (member, value) in self.members.iter().zip(members) {
+            s.values.insert(member.clone(), value);
         }
         Some(s)
     }
 }
 
 impl VirtualMachine {
-    pub fn new(mut new_rom: Vec<Instruction>, functions: HashMap<String, usize>, class_definitions: HashMap<String, ObjectCreator>) -> Self {
+    pub fn new(mut new_rom: Vec<Instruction>, functions: HashMap<String, usize>, class_definitions: HashMap<String, ObjectCreator>, source_map: Vec<Span>) -> Self {
         let mut rom = vec![Instruction::Nop];
         rom.append(&mut new_rom);
         Self {
             stack: Vec::new(),
             rom,
             pc: 0,
-            heap: HashMap::new(),
+            globals: HashMap::new(),
+            heap: Heap::new(),
             functions,
             call_stack: vec![],
-            class_definitions
+            class_definitions,
+            source_map,
+            handlers: vec![],
+            thrown: None,
+            language: Language::Bisaya,
+            random: Random::from_time(),
+            debug: false,
         }
     }
-    pub fn emulate(instructions: Vec<Instruction>, functions: HashMap<String, usize>, classes: HashMap<String, ObjectCreator>) {
-        Self::new(instructions, functions, classes).run();
+    /// variables declared right now go to the innermost frame, or the globals outside of a function
+    fn current_scope(&mut self) -> &mut HashMap<String, Value> {
+        match self.call_stack.last_mut() {
+            Some(frame) => &mut frame.locals,
+            None => &mut self.globals
+        }
+    }
+    /// finds the scope a variable lives in, locals shadow globals
+    fn scope_of(&mut self, name: &str) -> Option<&mut HashMap<String, Value>> {
+        if let Some(frame) = self.call_stack.last_mut() {
+            if frame.locals.contains_key(name) {
+                return Some(&mut frame.locals);
+            }
+        }
+        if self.globals.contains_key(name) {
+            return Some(&mut self.globals);
+        }
+        None
+    }
+    /// the value a dotted name like p.pos.x points at, every part after the variable is a member
+    fn load_path(&mut self, name: &str) -> Result<Value, RuntimeError> {
+        let mut path = name.split('.');
+        let var = path.next().unwrap();
+        let mut value = match self.scope_of(var).and_then(|scope| scope.get(var)) {
+            Some(value) => value.clone(),
+            None => return Err(RuntimeError::from_kind(ErrorKind::UndefinedVariable(var.to_string())))
+        };
+        for member in path {
+            value = member_of(&value, member)?;
+        }
+        Ok(value)
+    }
+    /// the rom starts with a Nop the compiler doesn't know about, so the source map is off by one
+    fn span_at(&self, pc: usize) -> Option<Span> {
+        pc.checked_sub(1).and_then(|index| self.source_map.get(index)).copied()
     }
     fn current_instruction(&self) -> Instruction {
         self.rom[self.pc].clone()
@@ -1033,307 +1959,503 @@ impl VirtualMachine {
         self.pc < self.rom.len()
     }
 
-    fn single_run(&mut self, instruction: Instruction){
+    fn pop(&mut self) -> Result<Value, RuntimeError> {
+        self.stack.pop().ok_or_else(|| RuntimeError::from_kind(ErrorKind::StackUnderflow))
+    }
+    fn pop_operands(&mut self) -> Result<(ValueType, ValueType), RuntimeError> {
+        if self.stack.len() < 2 {
+            return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
+        }
+        let rhs = self.pop()?.to_val()?;
+        let lhs = self.pop()?.to_val()?;
+        Ok(ValueType::widen_beside_big_int(lhs, rhs))
+    }
+    /// anything can be compared to wala, even objects, so == and != check for it before looking at the types,
+    /// Some tells whether both sides are wala when at least one of them is
+    fn pop_wala_comparison(&mut self) -> Result<Option<bool>, RuntimeError> {
+        if self.stack.len() < 2 {
+            return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
+        }
+        let is_wala = |value: &Value| matches!(value, Value::Value(ValueType::Wala));
+        let (lhs, rhs) = (is_wala(&self.stack[self.stack.len() - 2]), is_wala(&self.stack[self.stack.len() - 1]));
+        if !lhs && !rhs {
+            return Ok(None)
+        }
+        self.stack.truncate(self.stack.len() - 2);
+        Ok(Some(lhs && rhs))
+    }
+    fn pop_condition(&mut self) -> Result<bool, RuntimeError> {
+        match self.pop()?.to_val()? {
+            ValueType::Bool(b) => Ok(b.value),
+            val => Err(RuntimeError::from_kind(ErrorKind::ConditionNotBool(val.type_name())))
+        }
+    }
+
+    fn single_run(&mut self, instruction: Instruction) -> Result<(), RuntimeError> {
         match instruction {
             Instruction::Add => {
-                if self.stack.len() < 2 {
-                    println!("{:?}", self.stack);
-                    panic!("Stack underflow");
-                }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.add(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                // adding two lists makes a new one
+                let sum = Value::Value(lhs.add(rhs)?);
+                self.heap.track(&sum);
+                self.stack.push(sum)
             }
             Instruction::Sub => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
-                }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.sub(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.sub(rhs)?))
             }
             Instruction::Mul => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
-                }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.mul(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.mul(rhs)?))
             }
             Instruction::Div => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
-                }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.div(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.div(rhs)?))
+            }
+            Instruction::Mod => {
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.rem(rhs)?))
+            }
+            Instruction::FloorDiv => {
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.floor_div(rhs)?))
+            }
+            Instruction::Pow => {
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.pow(rhs)?))
+            }
+            Instruction::Neg => {
+                let value = self.pop()?.to_val()?;
+                self.stack.push(Value::Value(value.neg()?))
+            }
+            Instruction::BitAnd => {
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.bit_and(rhs)?))
+            }
+            Instruction::BitOr => {
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.bit_or(rhs)?))
+            }
+            Instruction::BitXor => {
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.bit_xor(rhs)?))
+            }
+            Instruction::Shl => {
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.shl(rhs)?))
+            }
+            Instruction::Shr => {
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.shr(rhs)?))
+            }
+            Instruction::BitNot => {
+                let value = self.pop()?.to_val()?;
+                self.stack.push(Value::Value(value.bit_not()?))
             }
             Instruction::Eq => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
+                if let Some(same) = self.pop_wala_comparison()? {
+                    self.stack.push(Value::from_bool(same));
+                    return Ok(())
                 }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.eq(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.eq(rhs)?))
             }
             Instruction::Neq => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
+                if let Some(same) = self.pop_wala_comparison()? {
+                    self.stack.push(Value::from_bool(!same));
+                    return Ok(())
                 }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.ne(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.ne(rhs)?))
             }
             Instruction::Lt => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
-                }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.lt(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.lt(rhs)?))
             }
             Instruction::Gt => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
-                }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.gt(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.gt(rhs)?))
             }
             Instruction::LtEq => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
-                }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.le(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.le(rhs)?))
             }
             Instruction::GtEq => {
-                if self.stack.len() < 2 {
-                    panic!("Stack underflow");
-                }
-
-                let rhs = self.stack.pop().unwrap().to_val();
-                let lhs = self.stack.pop().unwrap().to_val();
-                self.stack.push(
-                    Value::Value(lhs.ge(rhs))
-                )
+                let (lhs, rhs) = self.pop_operands()?;
+                self.stack.push(Value::Value(lhs.ge(rhs)?))
             }
             Instruction::Load(name) => {
-                let mut path: VecDeque<&str> = name.split(".").into_iter().collect::<VecDeque<&str>>();
-                let var = path.pop_front().unwrap();
-                if let Some(val) = self.heap.get_mut(&var.to_string()) {
-                    let name = path.into_iter().collect::<Vec<&str>>().join(".");
-                    if name.is_empty(){
-                        match val.get_value() {
-                            None => {
-                                let val = self.heap.get(&var.to_string()).unwrap().clone();
-                                self.stack.push(Value::Object(val));
-                            }
-                            Some(value) => {self.stack.push(Value::Value(value))}
-                        }
-                    } else {
-                        self.stack.push(Value::Value(val.get_member(name)));
-                    }
-                } else {
-                    panic!("Struct {:?} not found", name);
-                }
+                let value = self.load_path(&name)?;
+                self.stack.push(value);
             }
             Instruction::Store(name) => {
-                let mut path: VecDeque<&str> = name.split(".").into_iter().collect::<VecDeque<&str>>();
-                // todo: struct member store
-                let var = path.pop_front().unwrap();
-                if let Some(val) = self.heap.get_mut(&var.to_string()) {
-                    let name = path.into_iter().collect::<Vec<&str>>().join(".");
-                    match self.stack.pop().unwrap(){
-                        Value::Value(v) => {
-                            if name.is_empty() {
-                                val.set_value(Value::Value(v));
-                            } else {
-                                val.set_member(name, Value::Value(v));
-                            }
-                        },
-                        Value::Object(obj) => {
-                            if name.is_empty() {
-                                self.heap.insert(var.to_string(), obj);
-                            } else {
-                                val.set_member(name, Value::Object(obj));
-                            }
-                        }
-                    };
-                } else {
-                    panic!("Struct not found");
+                let value = self.pop()?;
+                match name.rsplit_once('.') {
+                    None => match self.scope_of(&name) {
+                        Some(scope) => { scope.insert(name, value); }
+                        None => return Err(RuntimeError::from_kind(ErrorKind::UndefinedVariable(name)))
+                    },
+                    Some((parent, member)) => match self.load_path(parent)? {
+                        Value::Object(obj) => obj.borrow_mut().set_member(member.to_string(), value),
+                        Value::Value(val) => return Err(RuntimeError::from_kind(ErrorKind::NoMembers(val.type_name())))
+                    }
                 }
             }
             Instruction::NewVariable(name) => {
-                if self.stack.is_empty() {
-                    panic!("Stack underflow");
-                }
-
-                let value = match self.stack.pop().unwrap(){
-                    Value::Value(v) => {
-                        let mut sd = Object::new("".to_string());
-                        sd.set_value(Value::Value(v));
-                        sd
-                    },
-                    Value::Object(obj) => obj
-                };
-                match self.heap.insert(name.clone(), value) {
-                    None => {}
-                    Some(_) => { panic!("{} already exists", name) }
+                let value = self.pop()?;
+                if self.current_scope().insert(name.clone(), value).is_some() {
+                    return Err(RuntimeError::from_kind(ErrorKind::AlreadyDeclared(name)));
                 }
             }
             Instruction::Delete(name) => {
-                match self.heap.remove(&*name) {
-                    None => { panic!("{} doesn't exists", name) }
-                    Some(_) => {}
+                if self.current_scope().remove(&*name).is_none() {
+                    return Err(RuntimeError::from_kind(ErrorKind::UndefinedVariable(name)));
                 }
             }
             Instruction::Jump(line) => {
                 self.pc = line - 1;
             }
             Instruction::JumpIfFalse(line) => {
-                if let Some(val) = self.stack.pop() {
-                    let res = match val.to_val() {
-                        ValueType::Bool(b) => b.value,
-                        _ => panic!("Invalid type")
-                    };
-                    if !res {
-                        self.pc = line - 1;
-                    }
-                } else {
-                    panic!("Stack underflow");
+                if !self.pop_condition()? {
+                    self.pc = line - 1;
                 }
             }
             Instruction::JumpIfTrue(line) => {
-                if let Some(val) = self.stack.pop() {
-                    let res = match val.to_val() {
-                        ValueType::Bool(b) => b.value,
-                        _ => panic!("Invalid type")
-                    };
-                    if res {
-                        self.pc = line - 1;
-                    }
-                } else {
-                    panic!("Stack underflow");
+                if self.pop_condition()? {
+                    self.pc = line - 1;
                 }
             }
             Instruction::Call(function_name) => {
                 if BUILTIN_FUNCTIONS.contains(&&*function_name){
-                    self.call_builtin(&*function_name);
+                    self.call_builtin(&function_name)?;
                 } else if self.class_definitions.contains_key(&*function_name) {
-                    self.class_call(&*function_name)
+                    self.class_call(&function_name)?;
                 }
                 else if let Some(line) = self.functions.get(&function_name) {
-                    self.call_stack.push(self.pc);
+                    let line = *line;
+                    let stack_base = match self.stack.last() {
+                        Some(Value::Value(ValueType::Int(count))) => self.stack.len().saturating_sub(count.as_i64() as usize + 1),
+                        _ => self.stack.len(),
+                    };
+                    self.call_stack.push(Frame {
+                        function_name,
+                        return_address: self.pc,
+                        locals: HashMap::new(),
+                        stack_base,
+                    });
                     self.pc = line - 1;
+                } else if let Some((receiver, method_name)) = function_name.rsplit_once('.') {
+                    // obj.method(args), the receiver goes under the arguments like CallMethod expects
+                    let len = self.top_as_len()?;
+                    let receiver = self.load_path(receiver)?;
+                    self.stack.insert(self.stack.len() - len, receiver);
+                    self.stack.push(Value::Value(ValueType::Int(IntValue::Int32(len as i32))));
+                    self.method_call(method_name)?;
                 } else {
-                    panic!("Function not found");
+                    return Err(RuntimeError::from_kind(ErrorKind::FunctionNotFound(function_name)));
+                }
+            }
+            Instruction::CallMethod(method_name) => {
+                self.method_call(&method_name)?;
+            }
+            Instruction::LoadMember(name) => {
+                let value = self.pop()?;
+                self.stack.push(member_of(&value, &name)?);
+            }
+            Instruction::Index => {
+                let index = self.pop()?.to_val()?;
+                let item = match self.pop()? {
+                    Value::Value(ValueType::Vector(list)) => {
+                        let list = list.borrow();
+                        list[list_index(&index, list.len())?].clone()
+                    }
+                    Value::Value(ValueType::Map(map)) => {
+                        let key = MapKey::new(&index)?;
+                        match map.borrow().get(&key) {
+                            Some(value) => value.clone(),
+                            None => return Err(RuntimeError::from_kind(ErrorKind::KeyNotFound(key.to_string())))
+                        }
+                    }
+                    Value::Value(ValueType::String(string)) => Value::Value(ValueType::String(string.char_at(&index)?)),
+                    value => return Err(RuntimeError::from_kind(ErrorKind::NotIndexable(value.type_name())))
+                };
+                self.stack.push(item);
+            }
+            Instruction::StoreIndex => {
+                let value = self.pop()?;
+                let index = self.pop()?.to_val()?;
+                match self.pop()? {
+                    Value::Value(ValueType::Vector(list)) => {
+                        let mut list = list.borrow_mut();
+                        let position = list_index(&index, list.len())?;
+                        list[position] = value;
+                    }
+                    Value::Value(ValueType::Map(map)) => {
+                        map.borrow_mut().insert(MapKey::new(&index)?, value);
+                    }
+                    target => return Err(RuntimeError::from_kind(ErrorKind::NotIndexable(target.type_name())))
+                }
+            }
+            Instruction::BuildList(length) => {
+                if self.stack.len() < length {
+                    return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
+                }
+                let items = self.stack.split_off(self.stack.len() - length);
+                let list = Value::Value(ValueType::new_list(items));
+                self.heap.track(&list);
+                self.stack.push(list);
+            }
+            Instruction::BuildMap(length) => {
+                if self.stack.len() < length * 2 {
+                    return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
+                }
+                let mut map = MapValue::default();
+                let mut entries = self.stack.split_off(self.stack.len() - length * 2).into_iter();
+                while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+                    map.insert(MapKey::new(&key.to_val()?)?, value);
+                }
+                let map = Value::Value(ValueType::new_map(map));
+                self.heap.track(&map);
+                self.stack.push(map);
+            }
+            Instruction::Iterable => {
+                let iterable = match self.pop()? {
+                    list @ Value::Value(ValueType::Vector(_)) => list,
+                    Value::Value(ValueType::Map(map)) => {
+                        let keys = Value::Value(ValueType::new_list(map.borrow().keys().map(MapKey::to_value).collect()));
+                        self.heap.track(&keys);
+                        keys
+                    }
+                    Value::Value(ValueType::String(string)) => {
+                        let chars = Value::Value(ValueType::new_list(string.chars().map(|c| Value::Value(ValueType::String(c))).collect()));
+                        self.heap.track(&chars);
+                        chars
+                    }
+                    value => return Err(RuntimeError::from_kind(ErrorKind::NotIterable(value.type_name())))
+                };
+                self.stack.push(iterable);
+            }
+            Instruction::Unpack(count) => {
+                let items = match self.pop()? {
+                    Value::Value(ValueType::Vector(list)) => list.borrow().clone(),
+                    value => return Err(RuntimeError::from_kind(ErrorKind::CannotUnpack { value: value.type_name(), count }))
+                };
+                if items.len() != count {
+                    return Err(RuntimeError::from_kind(ErrorKind::UnpackCount { length: items.len(), count }));
+                }
+                self.stack.extend(items.into_iter().rev());
+            }
+            Instruction::TryBegin(line) => {
+                let variables = self.current_scope().keys().cloned().collect();
+                self.handlers.push(Handler {
+                    address: line,
+                    stack_len: self.stack.len(),
+                    frames: self.call_stack.len(),
+                    variables,
+                });
+            }
+            Instruction::TryEnd => {
+                if self.handlers.pop().is_none() {
+                    return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
                 }
             }
+            Instruction::Throw => {
+                let message = match self.pop()? {
+                    Value::Object(obj) if obj.borrow().name == ERROR_CLASS => {
+                        let message = obj.borrow().get_member("message")?.as_string();
+                        self.thrown = Some(obj);
+                        message
+                    }
+                    Value::Value(ValueType::String(string)) => string.value,
+                    value => value.as_string(),
+                };
+                return Err(RuntimeError::from_kind(ErrorKind::Thrown(message)));
+            }
             Instruction::Push(value) => {
                 self.stack.push(Value::Value(value));
             }
-            Instruction::Pop => { unimplemented!("Pop"); }
+            Instruction::Pop => {
+                self.pop()?;
+            }
             Instruction::Nop => {}
             Instruction::Return => {
-                if let Some(line) = self.call_stack.pop() {
-                    self.pc = line;
-                } else {
-                    panic!("Call stack underflow");
+                match self.call_stack.pop() {
+                    Some(frame) => {
+                        if self.debug && self.stack.len() != frame.stack_base + 1 {
+                            return Err(RuntimeError::from_kind(ErrorKind::UnbalancedStack { expected: frame.stack_base + 1, found: self.stack.len() }));
+                        }
+                        self.pc = frame.return_address
+                    }
+                    None => return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow))
                 }
             }
             Instruction::Clone => {
-                if let Some(val) = self.stack.pop() {
-                    self.stack.push(val.clone());
-                    self.stack.push(val);
-                } else {
-                    panic!("Stack underflow");
-                }
+                let val = self.pop()?;
+                self.stack.push(val.clone());
+                self.stack.push(val);
             }
             Instruction::Swap => {
                 if self.stack.len() < 2 {
-                    panic!("Stack underflow");
+                    return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
                 }
 
-                let rhs = self.stack.pop().unwrap();
-                let lhs = self.stack.pop().unwrap();
+                let rhs = self.pop()?;
+                let lhs = self.pop()?;
                 self.stack.push(rhs);
                 self.stack.push(lhs);
             }
             Instruction::Rotate => {
                 if self.stack.len() < 3 {
-                    panic!("Stack underflow");
+                    return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
                 }
-                let rhs = self.stack.pop().unwrap();
-                let mid = self.stack.pop().unwrap();
-                let lhs = self.stack.pop().unwrap();
+                let rhs = self.pop()?;
+                let mid = self.pop()?;
+                let lhs = self.pop()?;
                 self.stack.push(mid);
                 self.stack.push(lhs);
                 self.stack.push(rhs);
             }
             Instruction::MoveBack(amount) => {
-                if self.stack.len() < amount {
-                    panic!("Stack underflow");
+                if self.stack.len() <= amount {
+                    return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
                 }
-                let to_move = self.stack.pop().unwrap();
+                let to_move = self.pop()?;
                 self.stack.insert(self.stack.len() - amount, to_move);
             }
         }
+        Ok(())
     }
-    fn top_as_len(&mut self, name: &str) -> i64 {
-        match self.stack.pop().unwrap_or_else(|| panic!("{} Call, Stack underflow", name)).to_val() {
-            ValueType::Int(int) => int.as_i64(),
-            _ => panic!("{} Call, Invalid type", name)
+    fn top_as_len(&mut self) -> Result<usize, RuntimeError> {
+        let len = match self.pop()?.to_val()? {
+            ValueType::Int(int) => int.as_i64() as usize,
+            _ => return Err(RuntimeError::from_kind(ErrorKind::MissingArgumentCount))
+        };
+        if len > self.stack.len() {
+            return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow));
         }
+        Ok(len)
     }
-    fn class_call(&mut self, name: &str){
-        let len = self.top_as_len(&*format!("Struct Constructor {}", name));
-        let mut args = Vec::from_iter(self.stack.drain(self.stack.len() - len as usize..).into_iter());
-        let creator = self.class_definitions.get(&*name).unwrap();
-        // println!("{:?}", self.class_definitions);
+    fn class_call(&mut self, name: &str) -> Result<(), RuntimeError> {
+        let len = self.top_as_len()?;
+        let args = Vec::from_iter(self.stack.drain(self.stack.len() - len..));
+        let creator = self.class_definitions.get(name).unwrap();
         match creator.create(args) {
             Some(obj) => {
-                self.stack.push(Value::Object(obj));
-            }
-            None => { panic!("Struct {} not found", name) }
-        }
-    }
-    fn call_builtin(&mut self, name: &str){
-        let len = self.top_as_len(&*format!("Function {}", name));
-        let mut args = VecDeque::from_iter(self.stack.drain(self.stack.len() - len as usize..).into_iter());
+                let obj = Value::new_object(obj);
+                self.heap.track(&obj);
+                self.stack.push(obj);
+                Ok(())
+            }
+            None => Err(RuntimeError::argument_count(name, creator.members.len(), Some(creator.members.len()), len))
+        }
+    }
+    /// the receiver sits under the arguments and is passed along as the first one, kani
+    fn method_call(&mut self, name: &str) -> Result<(), RuntimeError> {
+        let len = self.top_as_len()?;
+        let receiver = match self.stack.len().checked_sub(len + 1).map(|index| &self.stack[index]) {
+            Some(receiver) => receiver,
+            None => return Err(RuntimeError::from_kind(ErrorKind::StackUnderflow))
+        };
+        let class_name = match receiver {
+            Value::Object(obj) => obj.borrow().name.clone(),
+            Value::Value(ValueType::String(_)) => return self.string_method(name, len),
+            Value::Value(val) => return Err(RuntimeError::from_kind(ErrorKind::CannotCallMethod { method: name.to_string(), value: val.type_name() }))
+        };
+        let location = match self.class_definitions.get(&class_name).and_then(|creator| creator.get_method(name)) {
+            Some(location) => location,
+            None => return Err(RuntimeError::from_kind(ErrorKind::MethodNotFound { class: class_name, method: name.to_string() }))
+        };
+        self.stack.push(Value::Value(ValueType::Int(IntValue::Int32(len as i32 + 1))));
+        self.call_stack.push(Frame {
+            function_name: format!("{}.{}", class_name, name),
+            return_address: self.pc,
+            locals: HashMap::new(),
+            stack_base: self.stack.len() - len - 2,
+        });
+        self.pc = location - 1;
+        Ok(())
+    }
+    /// the methods every string has, the string sits under its `len` arguments like any other receiver
+    fn string_method(&mut self, name: &str, len: usize) -> Result<(), RuntimeError> {
+        let args: Vec<ValueType> = self.stack.drain(self.stack.len() - len..).map(|arg| arg.to_val()).collect::<Result<_, _>>()?;
+        let Some(Value::Value(ValueType::String(string))) = self.stack.pop() else { unreachable!("string method without a string") };
+        let expect_args = |min: usize, max: usize| match (min..=max).contains(&args.len()) {
+            true => Ok(()),
+            false => Err(RuntimeError::argument_count(name, min, Some(max), args.len())),
+        };
+        let text_arg = |index: usize| match &args[index] {
+            ValueType::String(text) => Ok(text.value.as_str()),
+            val => Err(RuntimeError::argument_type(name, &["string"], val.type_name())),
+        };
+        let int_arg = |index: usize| match &args[index] {
+            ValueType::Int(int) => Ok(int.as_i64()),
+            val => Err(RuntimeError::argument_type(name, &["int"], val.type_name())),
+        };
+        let text = |value: String| Value::Value(ValueType::String(StringValue::new(value)));
+        let result = match name {
+            "length" => {
+                expect_args(0, 0)?;
+                Value::Value(ValueType::Int(IntValue::Int64(string.length() as i64)))
+            }
+            "upper" => { expect_args(0, 0)?; text(string.value.to_uppercase()) }
+            "lower" => { expect_args(0, 0)?; text(string.value.to_lowercase()) }
+            "trim" => { expect_args(0, 0)?; text(string.value.trim().to_string()) }
+            "contains" => { expect_args(1, 1)?; Value::from_bool(string.value.contains(text_arg(0)?)) }
+            "starts_with" => { expect_args(1, 1)?; Value::from_bool(string.value.starts_with(text_arg(0)?)) }
+            "ends_with" => { expect_args(1, 1)?; Value::from_bool(string.value.ends_with(text_arg(0)?)) }
+            "find" => {
+                expect_args(1, 1)?;
+                Value::Value(ValueType::Int(IntValue::Int64(string.find(text_arg(0)?))))
+            }
+            "replace" => {
+                expect_args(2, 2)?;
+                text(string.value.replace(text_arg(0)?, text_arg(1)?))
+            }
+            "slice" => {
+                expect_args(1, 2)?;
+                let end = if args.len() == 2 { int_arg(1)? } else { string.length() as i64 };
+                Value::Value(ValueType::String(string.slice(int_arg(0)?, end)))
+            }
+            "split" | "chars" => {
+                let parts = match name {
+                    "chars" => { expect_args(0, 0)?; string.chars().collect() }
+                    _ if args.is_empty() => string.split(None),
+                    _ => {
+                        expect_args(0, 1)?;
+                        let separator = text_arg(0)?;
+                        if separator.is_empty() {
+                            return Err(RuntimeError::from_kind(ErrorKind::EmptySeparator));
+                        }
+                        string.split(Some(separator))
+                    }
+                };
+                let list = Value::Value(ValueType::new_list(parts.into_iter().map(|part| Value::Value(ValueType::String(part))).collect()));
+                self.heap.track(&list);
+                list
+            }
+            "join" => {
+                // ", ".join(xs) puts the string between every item of the list
+                expect_args(1, 1)?;
+                let ValueType::Vector(list) = &args[0] else {
+                    return Err(RuntimeError::argument_type(name, &["list"], args[0].type_name()));
+                };
+                let parts: Vec<String> = list.borrow().iter().map(Value::as_string).collect();
+                text(parts.join(&string.value))
+            }
+            _ => return Err(RuntimeError::from_kind(ErrorKind::MethodNotFound { class: "string".to_string(), method: name.to_string() }))
+        };
+        self.stack.push(result);
+        Ok(())
+    }
+    fn call_builtin(&mut self, name: &str) -> Result<(), RuntimeError> {
+        let len = self.top_as_len()?;
+        let mut args = VecDeque::from_iter(self.stack.drain(self.stack.len() - len..));
+        let stack_len = self.stack.len();
         match name {
             "print" => {
                 let mut output = String::new();
                 for arg in args {
-                    output.push_str(&*arg.as_string());
+                    output.push_str(&arg.as_string());
                     output.push(' ')
                 }
                 output.pop();
@@ -1342,25 +2464,23 @@ impl VirtualMachine {
             "println" => {
                 let mut output = String::new();
                 for arg in args {
-                    output.push_str(&*arg.as_string());
+                    output.push_str(&arg.as_string());
                     output.push(' ')
                 }
                 output.pop();
                 println!("{}", output)
             },
             "format" => {
-                if args.len() < 1 {
-                    panic!("format function takes 1 or more arguments, {} given", args.len())
+                if args.is_empty() {
+                    return Err(RuntimeError::argument_count(name, 1, None, args.len()));
                 }
 
-                let mut string = match args.pop_front() {
-                    Some(Value::Value(ValueType::String(string))) => string.value,
-                    _ => panic!("format function takes string as first argument")
+                let template = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::String(string)) => string.value,
+                    value => return Err(RuntimeError::argument_type(name, &["string"], value.type_name()))
                 };
 
-                for arg in args {
-                    string = string.replacen("{}", &*arg.as_string(), 1)
-                }
+                let string = format_spec::format(&template, args.make_contiguous()).map_err(|error| RuntimeError::from_kind(ErrorKind::Format(error)))?;
                 self.stack.push(
                     Value::Value(ValueType::String(StringValue {
                         value: string
@@ -1369,105 +2489,801 @@ impl VirtualMachine {
             }
             "assert" => {
                 if args.len() < 2 {
-                    panic!("assert function takes 2 arguments, {} given", args.len())
+                    return Err(RuntimeError::argument_count(name, 2, None, args.len()));
                 }
 
-                let mut res = match args.pop_front() {
-                    Some(Value::Value(ValueType::Bool(bool))) => bool.value,
-                    res => panic!("assert function takes bool as first argument, got: {:?}", res)
+                let res = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Bool(bool)) => bool.value,
+                    value => return Err(RuntimeError::argument_type(name, &["bool"], value.type_name()))
                 };
 
                 if !res {
                     let mut output = String::new();
                     for arg in args {
-                        output.push_str(&*arg.as_string());
+                        output.push_str(&arg.as_string());
                         output.push(' ')
                     }
                     output.pop();
-                    panic!("Assertion failed: {}", output)
+                    return Err(RuntimeError::from_kind(ErrorKind::AssertionFailed(output)));
                 }
             }
             "push" => {
                 if args.len() != 2 {
-                    panic!("push function takes 2 arguments, {} given", args.len())
+                    return Err(RuntimeError::argument_count(name, 2, Some(2), args.len()));
                 }
 
-                let mut list = match args.pop_front() {
-                    Some(Value::Value(ValueType::Vector(list))) => list,
-                    val => panic!("push function takes list as first argument: {:?}", val)
+                let list = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Vector(list)) => list,
+                    value => return Err(RuntimeError::argument_type(name, &["list"], value.type_name()))
                 };
-                let value = args.pop_front().unwrap_or_else(|| panic!("push function takes value as second argument"));
-                list.push(value);
-                self.stack.push(Value::Value(ValueType::Vector(list)))
+                list.borrow_mut().push(args.pop_front().unwrap());
             }
             "pop" => {
                 if args.len() != 1 {
-                    panic!("pop function takes 1 argument, {} given", args.len())
+                    return Err(RuntimeError::argument_count(name, 1, Some(1), args.len()));
                 }
 
-                let mut list = match args.pop_front() {
-                    Some(Value::Value(ValueType::Vector(list))) => list,
-                    val => panic!("pop function takes list as first argument: {:?}", val)
+                let list = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Vector(list)) => list,
+                    value => return Err(RuntimeError::argument_type(name, &["list"], value.type_name()))
                 };
-                let value = list.pop().unwrap_or_else(|| panic!("pop function takes list as first argument"));
-                self.stack.push(Value::Value(ValueType::Vector(list)));
+                let value = list.borrow_mut().pop().ok_or_else(|| RuntimeError::from_kind(ErrorKind::EmptyList(name.to_string())))?;
                 self.stack.push(value)
             }
             "read_element" => {
                 if args.len() != 2 {
-                    panic!("read_element function takes 2 arguments, {} given", args.len())
+                    return Err(RuntimeError::argument_count(name, 2, Some(2), args.len()));
                 }
 
-                let list = match args.pop_front() {
-                    Some(Value::Value(ValueType::Vector(list))) => list,
-                    val => panic!("read_element function takes list as first argument: {:?}", val)
-                };
-                let index = match args.pop_front() {
-                    Some(Value::Value(ValueType::Int(int))) => int.as_i64(),
-                    val => panic!("read_element function takes int as second argument: {:?}", val)
+                let list = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Vector(list)) => list,
+                    value => return Err(RuntimeError::argument_type(name, &["list"], value.type_name()))
                 };
-                let item = match list.get(index as usize) {
-                    Some(item) => item.clone(),
-                    None => panic!("read_element function takes index in range of list")
+                let list = list.borrow();
+                let index = match args.pop_front().unwrap() {
+                    Value::Value(index @ ValueType::Int(_)) => list_index(&index, list.len())?,
+                    value => return Err(RuntimeError::argument_type(name, &["int"], value.type_name()))
                 };
-                self.stack.push(Value::Value(ValueType::Vector(list)));
-                self.stack.push(item)
+                self.stack.push(list[index].clone())
             }
             "write_element" => {
                 if args.len() != 3 {
-                    panic!("write_element function takes 3 arguments, {} given", args.len())
+                    return Err(RuntimeError::argument_count(name, 3, Some(3), args.len()));
                 }
 
-                let mut list = match args.pop_front() {
-                    Some(Value::Value(ValueType::Vector(list))) => list,
-                    val => panic!("write_element function takes list as first argument: {:?}", val)
+                let list = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Vector(list)) => list,
+                    value => return Err(RuntimeError::argument_type(name, &["list"], value.type_name()))
                 };
-                let index = match args.pop_front() {
-                    Some(Value::Value(ValueType::Int(int))) => int.as_i64(),
-                    val => panic!("write_element function takes int as second argument: {:?}", val)
+                let mut list = list.borrow_mut();
+                let index = match args.pop_front().unwrap() {
+                    Value::Value(index @ ValueType::Int(_)) => list_index(&index, list.len())?,
+                    value => return Err(RuntimeError::argument_type(name, &["int"], value.type_name()))
                 };
-                let val = args.pop_front().unwrap_or_else(|| panic!("write_element function takes value as third argument"));
-                match list.get_mut(index as usize) {
-                    Some(item) => *item = val,
-                    None => panic!("write_element function takes index in range of list")
+                list[index] = args.pop_front().unwrap();
+            }
+            "length" => {
+                if args.len() != 1 {
+                    return Err(RuntimeError::argument_count(name, 1, Some(1), args.len()));
+                }
+
+                let length = match args.pop_front().unwrap().to_val()? {
+                    ValueType::Vector(list) => list.borrow().len(),
+                    ValueType::Map(map) => map.borrow().len(),
+                    ValueType::String(string) => string.length(),
+                    val => return Err(RuntimeError::argument_type(name, &["list", "map", "string"], val.type_name()))
+                };
+                self.stack.push(Value::Value(ValueType::Int(IntValue::Int64(length as i64))))
+            }
+            "keys" | "values" => {
+                if args.len() != 1 {
+                    return Err(RuntimeError::argument_count(name, 1, Some(1), args.len()));
+                }
+
+                let map = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Map(map)) => map,
+                    value => return Err(RuntimeError::argument_type(name, &["map"], value.type_name()))
                 };
-                self.stack.push(Value::Value(ValueType::Vector(list)))
+                let items = match name {
+                    "keys" => map.borrow().keys().map(MapKey::to_value).collect(),
+                    _ => map.borrow().values().cloned().collect(),
+                };
+                let list = Value::Value(ValueType::new_list(items));
+                self.heap.track(&list);
+                self.stack.push(list)
             }
-            _ => unimplemented!("builtin function {} is not implemented", name)
+            "naa" => {
+                if args.len() != 2 {
+                    return Err(RuntimeError::argument_count(name, 2, Some(2), args.len()));
+                }
+
+                let map = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Map(map)) => map,
+                    value => return Err(RuntimeError::argument_type(name, &["map"], value.type_name()))
+                };
+                let key = MapKey::new(&args.pop_front().unwrap().to_val()?)?;
+                let found = map.borrow().contains(&key);
+                self.stack.push(Value::from_bool(found))
+            }
+            "gc_stats" => {
+                if !args.is_empty() {
+                    return Err(RuntimeError::argument_count(name, 0, Some(0), args.len()));
+                }
+
+                let stats = self.heap.stats();
+                let mut object = Object::new("GcStats".to_string());
+                for (member, count) in [("collections", stats.collections), ("freed", stats.freed), ("live", stats.live), ("threshold", stats.threshold)] {
+                    object.set_member(member.to_string(), Value::Value(ValueType::Int(IntValue::Int64(count as i64))));
+                }
+                let object = Value::new_object(object);
+                self.heap.track(&object);
+                self.stack.push(object)
+            }
+            "random.binhi" => {
+                if args.len() != 1 {
+                    return Err(RuntimeError::argument_count(name, 1, Some(1), args.len()));
+                }
+
+                let seed = match args.pop_front().unwrap().to_val()? {
+                    ValueType::Int(seed) => seed.as_i64(),
+                    val => return Err(RuntimeError::argument_type(name, &["int"], val.type_name()))
+                };
+                self.random = Random::new(seed as u64);
+            }
+            "random.numero" => {
+                if args.len() != 2 {
+                    return Err(RuntimeError::argument_count(name, 2, Some(2), args.len()));
+                }
+
+                let (low, high) = match (args.pop_front().unwrap().to_val()?, args.pop_front().unwrap().to_val()?) {
+                    (ValueType::Int(low), ValueType::Int(high)) => (low.as_i64(), high.as_i64()),
+                    (ValueType::Int(_), val) | (val, _) => return Err(RuntimeError::argument_type(name, &["int"], val.type_name()))
+                };
+                if low > high {
+                    return Err(RuntimeError::from_kind(ErrorKind::RandomRange { low, high }));
+                }
+                let number = self.random.between(low, high);
+                self.stack.push(Value::Value(ValueType::Int(IntValue::Int64(number))))
+            }
+            "random.decimal" => {
+                if !args.is_empty() {
+                    return Err(RuntimeError::argument_count(name, 0, Some(0), args.len()));
+                }
+
+                let number = self.random.next_f64();
+                self.stack.push(Value::Value(ValueType::Float(FloatValue::Float64(number))))
+            }
+            "random.pilian" => {
+                if args.len() != 1 {
+                    return Err(RuntimeError::argument_count(name, 1, Some(1), args.len()));
+                }
+
+                let list = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Vector(list)) => list,
+                    value => return Err(RuntimeError::argument_type(name, &["list"], value.type_name()))
+                };
+                let list = list.borrow();
+                if list.is_empty() {
+                    return Err(RuntimeError::from_kind(ErrorKind::EmptyList(name.to_string())));
+                }
+                let index = self.random.below(list.len() as u64) as usize;
+                self.stack.push(list[index].clone())
+            }
+            "random.shuffle" => {
+                if args.len() != 1 {
+                    return Err(RuntimeError::argument_count(name, 1, Some(1), args.len()));
+                }
+
+                let list = match args.pop_front().unwrap() {
+                    Value::Value(ValueType::Vector(list)) => list,
+                    value => return Err(RuntimeError::argument_type(name, &["list"], value.type_name()))
+                };
+                let mut list = list.borrow_mut();
+                for index in (1..list.len()).rev() {
+                    let other = self.random.below(index as u64 + 1) as usize;
+                    list.swap(index, other);
+                }
+            }
+            _ => return Err(RuntimeError::from_kind(ErrorKind::FunctionNotFound(name.to_string())))
+        }
+        // every call gives back exactly one value, the builtins with nothing to give back give wala
+        if self.stack.len() == stack_len {
+            self.stack.push(Value::Value(ValueType::Wala));
+        }
+        Ok(())
+    }
+    /// every statement starts with nothing on the stack but what the frames under it left there,
+    /// a statement starts wherever the source map moves on to a new span
+    fn check_stack_balance(&self) -> Result<(), RuntimeError> {
+        let starts_statement = self.pc >= 2 && self.source_map.get(self.pc - 1) != self.source_map.get(self.pc - 2);
+        let base = self.call_stack.last().map_or(0, |frame| frame.stack_base);
+        if starts_statement && self.stack.len() != base {
+            return Err(RuntimeError::from_kind(ErrorKind::UnbalancedStack { expected: base, found: self.stack.len() }));
         }
+        Ok(())
     }
-    pub fn run(&mut self){
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
         loop {
-            self.single_run(self.current_instruction());
+            let pc = self.pc;
+            if self.debug {
+                // a broken program is not the user's error, so no dakpa gets to catch this
+                if let Err(mut error) = self.check_stack_balance() {
+                    error.span = self.span_at(pc);
+                    return Err(error);
+                }
+            }
+            if let Err(mut error) = self.single_run(self.current_instruction()) {
+                error.span = self.span_at(pc);
+                error.trace = self.call_stack.iter()
+                    .map(|frame| (frame.function_name.clone(), self.span_at(frame.return_address)))
+                    .collect();
+                match self.handlers.pop() {
+                    Some(handler) => self.catch(handler, error),
+                    None => return Err(error)
+                }
+            }
 
+            if self.heap.should_collect() {
+                self.collect_garbage();
+            }
             if !self.next_instruction() {
                 break;
             }
         }
+        if self.debug && !self.stack.is_empty() {
+            return Err(RuntimeError::from_kind(ErrorKind::UnbalancedStack { expected: 0, found: self.stack.len() }));
+        }
+        Ok(())
+    }
+
+    /// unwinds to the sulayi block of the handler and jumps to its dakpa with the error on the stack
+    fn catch(&mut self, handler: Handler, error: RuntimeError) {
+        let value = match self.thrown.take() {
+            Some(object) => Value::Object(object),
+            None => {
+                let message = match &error.kind {
+                    ErrorKind::Thrown(message) => message.clone(),
+                    kind => kind.message(self.language),
+                };
+                let line = error.span.map_or(0, |span| span.line);
+                let mut object = Object::new(ERROR_CLASS.to_string());
+                object.set_member("message".to_string(), Value::Value(ValueType::String(StringValue::new(message))));
+                object.set_member("line".to_string(), Value::Value(ValueType::Int(IntValue::Int64(line as i64))));
+                let object = Value::new_object(object);
+                self.heap.track(&object);
+                object
+            }
+        };
+        self.stack.truncate(handler.stack_len);
+        self.call_stack.truncate(handler.frames);
+        self.current_scope().retain(|name, _| handler.variables.contains(name));
+        self.stack.push(value);
+        self.pc = handler.address - 1;
+    }
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+    }
+    pub fn set_debug(&mut self, enabled: bool) {
+        self.debug = enabled;
+    }
+    /// the roots are everything the program can still name: the stack, every frame's locals and the globals
+    fn collect_garbage(&mut self) -> usize {
+        let roots = self.stack.iter()
+            .chain(self.call_stack.iter().flat_map(|frame| frame.locals.values()))
+            .chain(self.globals.values());
+        self.heap.collect(roots)
+    }
+    pub fn trace_gc(&mut self, enabled: bool) {
+        self.heap.trace = enabled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::diagnostics::Diagnostic;
+    use crate::instruction_compiler;
+    use crate::parser::parse_file_data;
+    use super::*;
+
+    /// compiles and runs a program with the stack balance checks on, the programs check
+    /// their own results with assert since the globals are deleted when they end
+    fn run(source: &str) -> Result<(), Diagnostic> {
+        let statements = parse_file_data(source)?;
+        let (instructions, functions, classes, source_map) = instruction_compiler::compile(statements, Path::new("test.bis"), &mut vec![])?;
+        let mut vm = VirtualMachine::new(instructions, functions, classes, source_map);
+        vm.set_debug(true);
+        Ok(vm.run()?)
+    }
+
+    fn passes(source: &str) {
+        if let Err(diagnostic) = run(source) {
+            panic!("{}", diagnostic.render(source, None, Language::English))
+        }
+    }
+
+    fn error(source: &str) -> String {
+        run(source).expect_err("the program should fail").kind.message(Language::English)
+    }
+
+    #[test]
+    fn every_listed_builtin_is_implemented() {
+        for name in BUILTIN_FUNCTIONS {
+            let mut vm = VirtualMachine::new(vec![], HashMap::new(), HashMap::new(), vec![]);
+            vm.stack.push(Value::Value(ValueType::Int(IntValue::Int32(0))));
+            let result = vm.call_builtin(name);
+            assert!(!matches!(result, Err(RuntimeError { kind: ErrorKind::FunctionNotFound(_), .. })), "{} is listed but not implemented", name);
+        }
+    }
+
+    #[test]
+    fn recursion_gets_a_frame_per_call() {
+        passes(r#"
+proseso fact(n) {
+    kon n <= 1 {
+        balik 1
+    }
+    balik n * fact(n - 1)
+}
+proseso even(n) {
+    kon n == 0 {
+        balik true
+    }
+    balik odd(n - 1)
+}
+proseso odd(n) {
+    kon n == 0 {
+        balik false
+    }
+    balik even(n - 1)
+}
+assert(fact(10) == 3628800, "fact", fact(10))
+assert(even(10), "even")
+assert(odd(7), "mutual recursion")
+"#);
     }
 
-    pub fn get_heap(&self) -> &HashMap<String, Object> {
-        &self.heap
+    #[test]
+    fn locals_shadow_globals_and_end_with_the_call() {
+        passes(r#"
+deklara x = 1
+proseso double(x) {
+    deklara y = x * 2
+    balik y
+}
+assert(double(5) == 10, "the argument shadows the global")
+assert(x == 1, "the global is untouched", x)
+"#);
+        assert_eq!(error("proseso f() {\n    deklara y = 1\n    balik y\n}\ndeklara a = f()\ndeklara z = y\n"), "Variable y not found");
     }
+
+    #[test]
+    fn runtime_errors_carry_their_position_and_call_trace() {
+        let error = run("deklara x = 1\ndeklara y = x + \"a\"\n").unwrap_err();
+        let span = error.span.unwrap();
+        assert_eq!((span.line, span.column), (2, 1));
+
+        let error = run("proseso f(x) {\n    balik x + \"a\"\n}\ndeklara y = f(1)\n").unwrap_err();
+        assert_eq!(error.span.unwrap().line, 2);
+        assert_eq!(error.trace.len(), 1);
+        assert_eq!(error.trace[0].0, "f");
+        assert_eq!(error.trace[0].1.unwrap().line, 4);
+    }
+
+    #[test]
+    fn para_over_a_range_leaves_out_the_end() {
+        passes(r#"
+deklara total = 0
+para i gikan 0 hangtod 5 {
+    total = total + i
+}
+assert(total == 10, "0 to 4", total)
+para i gikan 3 hangtod 3 {
+    assert(false, "an empty range runs nothing")
 }
+"#);
+    }
+
+    #[test]
+    fn para_over_a_list_with_buwag_and_tiwas() {
+        passes(r#"
+deklara total = 0
+para x sa [1, 2, 3, 4, 5, 6] {
+    kon x == 2 {
+        tiwas
+    }
+    kon x == 5 {
+        buwag
+    }
+    total = total + x
+}
+assert(total == 8, "1 + 3 + 4", total)
+para x sa [1] {
+    deklara inside = x
+}
+para x sa [2] {
+    deklara inside = x
+}
+"#);
+    }
 
+    const PERSON: &str = "klase Tao {\n    pangalan\n}\n";
+
+    #[test]
+    fn member_of_a_call_result() {
+        passes(&format!("{}assert(Tao(\"Juan\").pangalan == \"Juan\", \"call result\")\n", PERSON));
+    }
+
+    #[test]
+    fn missing_member_is_a_runtime_error() {
+        assert_eq!(error(&format!("{}deklara x = Tao(\"Juan\").edad\n", PERSON)), "Tao has no member named 'edad'");
+        assert_eq!(error("deklara x = (1).pangalan\n"), "int has no members");
+    }
+
+    #[test]
+    fn methods_see_their_object_as_kani() {
+        passes(r#"
+klase Counter {
+    count
+
+    proseso add(amount) {
+        kani.count = kani.count + amount
+        balik kani.get()
+    }
+    proseso get() {
+        balik kani.count
+    }
+}
+deklara counter = Counter(1)
+assert(counter.add(4) == 5, "add returns through another method")
+assert(counter.count == 5, "the member changed", counter.count)
+assert(Counter(7).get() == 7, "a method on a call result")
+"#);
+        assert_eq!(error("klase A {\n    x\n}\ndeklara a = A(1)\ndeklara y = a.wala_ni()\n"), "A has no method named 'wala_ni'");
+    }
+
+    #[test]
+    fn member_of_an_indexed_element() {
+        passes(&format!("{}deklara xs = [Tao(\"Ana\"), Tao(\"Ben\")]\ndeklara i = 1\nassert(xs[i].pangalan == \"Ben\", \"indexed\", xs[i].pangalan)\n", PERSON));
+    }
+
+    #[test]
+    fn index_reads_and_writes() {
+        passes("deklara xs = [10, 20, 30]\nxs[1] = 5\nassert(xs[1] == 5, \"write\")\nassert(xs[-1] == 30, \"from the back\")\n");
+        passes("deklara grid = [[1, 2], [3, 4]]\ngrid[1][0] = 9\nassert(grid[1][0] + grid[0][1] == 11, \"nested\")\n");
+        assert_eq!(error("deklara xs = [1, 2]\ndeklara x = xs[2]\n"), "Index 2 is out of range for a list of length 2");
+    }
+
+    #[test]
+    fn lists_and_objects_are_shared_not_copied() {
+        passes(&format!(r#"{}
+deklara xs = [1, 2]
+deklara same = xs
+push(same, 3)
+assert(length(xs) == 3, "push through the alias", xs)
+proseso rename(tao) {{
+    tao.pangalan = "Ben"
+    balik tao
+}}
+deklara tao = Tao("Ana")
+deklara renamed = rename(tao)
+assert(tao.pangalan == "Ben", "a proseso changes the caller's object")
+deklara people = [tao]
+tao.pangalan = "Cy"
+assert(people[0].pangalan == "Cy", "the list holds the same object")
+"#, PERSON));
+    }
+
+    #[test]
+    fn cycles_made_in_a_loop_get_collected() {
+        passes(r#"
+para i gikan 0 hangtod 3000 {
+    deklara xs = []
+    push(xs, xs)
+}
+deklara stats = gc_stats()
+assert(stats.collections > 0, "collected", stats.collections)
+assert(stats.freed > 2000, "freed", stats.freed)
+"#);
+    }
+
+    #[test]
+    fn maps_keep_insertion_order_and_share_int_keys() {
+        passes(r#"
+deklara ages = {"Juan": 20, "Maria": 31}
+ages["Pedro"] = 5
+ages["Juan"] = 21
+assert(ages["Juan"] == 21, "overwrite")
+assert(naa(ages, "Pedro"), "naa")
+kon naa(ages, "Jose") {
+    assert(false, "Jose is not in the map")
+}
+deklara names = keys(ages)
+assert(names[0] == "Juan", "keys stay in insertion order", names)
+assert(names[2] == "Pedro", "keys stay in insertion order", names)
+assert(values(ages)[2] == 5, "values follow the keys")
+deklara numbers = {1: "usa"}
+assert(numbers[100000 * 100000 - 9999999999] == "usa", "an int key is the same key at any width")
+"#);
+        assert_eq!(error("deklara m = {\"a\": 1}\ndeklara x = m[\"b\"]\n"), "Key 'b' is not in the map");
+        assert_eq!(error("deklara m = {[1]: 1}\n"), "Map keys must be int, bool or string, got list");
+    }
+
+    #[test]
+    fn ug_and_o_short_circuit() {
+        passes(r#"
+proseso boom() {
+    assert(false, "the right side should not run")
+    balik true
+}
+assert(dili (false ug boom()), "ug stops at false")
+assert(true o boom(), "o stops at true")
+assert((true && false) == false ug (false || true), "the symbol spellings")
+assert(!false ug dili dili true, "not")
+"#);
+        assert_eq!(error("deklara x = 1 ug true\n"), "Expected a bool for the condition, got int");
+    }
+
+    #[test]
+    fn modulo_and_floor_division_round_down() {
+        passes(r#"
+assert(-7 % 3 == 2 ug 7 % -3 == -2, "% takes the sign of the divisor")
+assert(-7 // 2 == -4 ug 7 // -2 == -4 ug 7 // 2 == 3, "// rounds toward negative infinity")
+assert(-7.5 // 2 == -4.0 ug 7.5 % 2 == 1.5, "floats too")
+"#);
+    }
+
+    #[test]
+    fn power_binds_tighter_than_unary_minus() {
+        passes(r#"
+assert(-2 ** 2 == -4, "-(2 ** 2)")
+assert(2 ** 3 ** 2 == 512, "right associative")
+assert(2 ** -1 == 0.5, "a negative exponent gives a float")
+assert(--3 == 3 ug -(1 - 4) == 3, "unary minus")
+"#);
+    }
+
+    #[test]
+    fn bitwise_operators_on_ints() {
+        passes(r#"
+assert((12 & 10) == 8 ug (12 | 10) == 14 ug (12 ^ 10) == 6, "and, or, xor")
+assert(~5 == -6 ug (-1 & 255) == 255, "two's complement")
+assert(1 << 40 == 1099511627776, "<< promotes instead of dropping bits")
+assert(-100 >> 2 == -25 ug -1 >> 100 == -1 ug 5 >> 100 == 0, ">> is arithmetic")
+assert(6 & 1 == 0, "comparisons come after the bitwise operators")
+"#);
+        assert_eq!(error("deklara x = 1 << -1\n"), "Negative shift count");
+        assert_eq!(error("deklara x = 1.0 & 1\n"), "Cannot bitwise and float and int");
+    }
+
+    #[test]
+    fn ints_widen_one_step_at_a_time_and_end_in_a_bigint() {
+        assert!(matches!(IntValue::Int8(127).add(IntValue::Int8(1)), IntValue::Int16(128)));
+        assert!(matches!(IntValue::Int16(-32768).sub(IntValue::Int8(1)), IntValue::Int32(-32769)));
+        assert!(matches!(IntValue::Int32(i32::MAX).mul(IntValue::Int8(2)), IntValue::Int64(_)));
+        let big = IntValue::Int64(i64::MAX).add(IntValue::Int8(1));
+        assert!(matches!(&big, IntValue::BigInt(value) if *value == BigInt::from(i64::MAX) + 1));
+        assert!(matches!(big.sub(IntValue::Int8(1)), IntValue::Int64(i64::MAX)), "a BigInt that fits again goes back to an Int64");
+    }
+
+    #[test]
+    fn bigints_behave_like_any_other_int() {
+        passes(r#"
+deklara big = 9223372036854775807 + 1
+assert(big == 9223372036854775808, "past i64")
+assert(big - 1 == 9223372036854775807, "and back")
+assert(2 ** 100 % 7 == 2 ug -(2 ** 100) // 3 == -422550200076076467165567735126, "% and // round down on bigints too")
+assert(1 << 70 == 2 ** 70, "<< promotes past i64")
+"#);
+    }
+
+    #[test]
+    fn ints_stop_growing_at_the_int_limit() {
+        assert_eq!(IntValue::Int8(-128).bits(), 8);
+        assert_eq!(IntValue::Int64(0).bits(), 0);
+        assert_eq!(IntValue::Int64(i64::MIN).bits(), 64);
+        assert_eq!(error("deklara x = 3\nsamtang x > 0 {\n    x = x * x\n}\n"), "Integer result is too big");
+        assert_eq!(error("deklara x = 2 ** 10000000\n"), "Integer result is too big");
+        assert_eq!(error("deklara x = 1 << 10000000\n"), "Integer result is too big");
+        passes("deklara x = 2 ** 100 * 2 ** 100\nassert(x == 2 ** 200, \"big products still work\")\n");
+    }
+
+    #[test]
+    fn integer_division_by_zero_is_an_error() {
+        passes(r#"
+deklara infinite = 1.0 / 0
+assert(infinite > 10 ** 300, "floats follow IEEE", infinite)
+assert(7 / 2 == 3, "ints truncate")
+"#);
+        assert_eq!(error("deklara x = 1 / 0\n"), "Integer division by zero");
+        assert_eq!(error("deklara x = 5 % 0\n"), "Integer division by zero");
+        assert_eq!(error("deklara x = 1 // 0\n"), "Integer division by zero");
+    }
+
+    #[test]
+    fn katapusan_runs_when_buwag_tiwas_and_balik_leave_the_block() {
+        passes(r#"
+deklara ran = 0
+para i gikan 0 hangtod 5 {
+    sulayi {
+        kon i == 1 {
+            tiwas
+        }
+        kon i == 3 {
+            buwag
+        }
+    } katapusan {
+        ran = ran * 10 + i
+    }
+}
+assert(ran == 123, "tiwas and buwag both run the katapusan", ran)
+
+proseso early() {
+    sulayi {
+        balik "gikan sa sulayi"
+    } katapusan {
+        ran = 0
+    }
+    balik "dili dapat"
+}
+assert(early() == "gikan sa sulayi" ug ran == 0, "balik keeps its value through the katapusan")
+"#);
+    }
+
+    #[test]
+    fn errors_unwind_out_of_calls_to_the_nearest_dakpa() {
+        passes(r#"
+deklara order = 0
+deklara caught = ""
+proseso deep(n) {
+    kon n == 0 {
+        ilabay "lalom"
+    }
+    sulayi {
+        deep(n - 1)
+    } katapusan {
+        order = order * 10 + n
+    }
+    balik n
+}
+sulayi {
+    deep(3)
+} dakpa err {
+    caught = err.message
+    assert(err.line == 6, "the line of the ilabay", err.line)
+}
+assert(order == 123 ug caught == "lalom", "inner katapusan first, then the outer dakpa", order)
+"#);
+        assert_eq!(error("sulayi {\n    ilabay \"x\"\n} katapusan {\n    deklara y = 1\n}\n"), "Uncaught error: x");
+    }
+
+    #[test]
+    fn random_module_is_repeatable_with_binhi() {
+        passes(r#"
+ipasulod { random }
+random.binhi(42)
+deklara first = [random.numero(1, 100), random.decimal(), random.pilian(["a", "b", "c"])]
+random.binhi(42)
+assert(random.numero(1, 100) == first[0] ug random.decimal() == first[1] ug random.pilian(["a", "b", "c"]) == first[2], "same seed", first)
+deklara xs = [1, 2, 3, 4, 5]
+random.shuffle(xs)
+deklara total = 0
+para x sa xs {
+    total = total + x
+}
+assert(length(xs) == 5 ug total == 15, "shuffle keeps every item", xs)
+"#);
+    }
+
+    #[test]
+    fn random_is_an_ordinary_name_until_imported() {
+        passes(r#"
+klase Dice {
+    side
+
+    proseso numero(low, high) {
+        balik kani.side
+    }
+}
+deklara random = Dice(6)
+assert(random.numero(1, 100) == 6, "the method on the variable, not the native function")
+random.numero(1, 2)
+"#);
+        assert_eq!(error("deklara x = random.decimal()\n"), "Variable random not found");
+    }
+
+    #[test]
+    fn string_methods_count_characters() {
+        passes(r#"
+deklara s = "  Señor, kumusta  ".trim()
+assert(s == "Señor, kumusta" ug s.length() == 14, "trim and length", s.length())
+assert(s.upper() == "SEÑOR, KUMUSTA" ug s.lower() == "señor, kumusta", "case")
+assert(s.find("kumusta") == 7 ug s.find("wala") == -1, "find counts characters")
+assert(s.slice(0, 5) == "Señor" ug s.slice(-7) == "kumusta" ug s.slice(20, 30) == "", "slice clamps")
+assert(s[2] == "ñ" ug s[-1] == "a", "indexing")
+assert(s.replace("kumusta", "maayo") == "Señor, maayo", "replace")
+assert(s.contains("ñ") ug s.starts_with("Se") ug s.ends_with("sta"), "contains, starts_with and ends_with")
+deklara parts = "a,b,,c".split(",")
+assert(length(parts) == 4 ug parts[2] == "", "split keeps empty parts")
+assert(length(" usa  duha ".split()) == 2, "split without a separator splits on whitespace")
+assert("-".join("abc".chars()) == "a-b-c", "chars and join")
+deklara letters = ""
+para c sa "ñoy" {
+    letters = c + letters
+}
+assert(letters == "yoñ", "para walks characters", letters)
+"#);
+        assert_eq!(error("deklara x = \"abc\".wala_ni()\n"), "string has no method named 'wala_ni'");
+    }
+
+    #[test]
+    fn f_strings_interpolate_expressions() {
+        passes(&format!(r#"{PERSON}
+deklara pangalan = "Juan"
+deklara edad = 31
+assert(f"Ang {{pangalan}} kay {{edad}} anyos" == "Ang Juan kay 31 anyos", "plain fields")
+assert(f"{{edad + 1}} {{pangalan.upper()}} {{[1, 2][1]}}" == "32 JUAN 2", "any expression goes in the braces")
+deklara tao = Tao("Ana")
+assert(f"{{tao.pangalan}}!" == "Ana!", "member reads")
+assert(f"[{{edad:>5}}] [{{2.5:.2}}] [{{255:x}}]" == "[   31] [2.50] [ff]", "format specs after a colon")
+assert(f"{{{{edad}}}} = {{edad}}" == "{{edad}} = 31", "doubled braces are text")
+assert(f"{{2 ** 64:x}}" == "10000000000000000", "bigints format too")
+assert(f"" == "" ug f"wala'y field" == "wala'y field", "no fields")
+"#));
+        assert_eq!(error("deklara x = f\"{y}\"\n"), "Variable y not found");
+    }
+
+    #[test]
+    fn wala_is_returned_and_compared() {
+        passes(&format!(r#"{PERSON}
+proseso walay_balik(x) {{
+    deklara y = x + 1
+}}
+proseso tingali(x) {{
+    kon x > 0 {{
+        balik x
+    }}
+}}
+assert(walay_balik(1) == wala ug tingali(-1) == wala ug tingali(2) == 2, "falling off the end gives wala")
+assert(wala == wala ug !(wala != wala), "wala equals itself")
+deklara tao = Tao("Ana")
+assert(tao != wala ug wala != tao ug 0 != wala ug "" != wala, "nothing else is wala")
+assert(f"{{wala}}" == "wala", "printing")
+"#));
+    }
+
+    #[test]
+    fn statements_leave_the_stack_balanced() {
+        // debug mode checks the stack is back to its base before every statement
+        passes(r#"
+deklara calls = 0
+proseso duha(x) {
+    calls = calls + 1
+    balik x, x * 2
+}
+deklara a, b = duha(3)
+assert(a == 3 ug b == 6, "multi assign takes the values in order", a, b)
+para i sa [1, 2, 3] {
+    duha(i)
+    deklara xs = [f"{i}"]
+    push(xs, i > 1 ug i < 3)
+    deklara s = xs[0]
+    s.upper()
+}
+samtang calls < 10 {
+    duha(calls)
+}
+assert(calls == 10, "calls as statements", calls)
+deklara c, d = duha(calls)
+assert(c == 10 ug d == 20, "multi assign after statement calls", c, d)
+assert(println("x") == wala, "builtins with nothing to give back give wala")
+"#);
+    }
+
+    #[test]
+    fn debug_mode_reports_an_unbalanced_stack() {
+        let mut vm = VirtualMachine::new(vec![Instruction::Push(ValueType::Wala)], HashMap::new(), HashMap::new(), vec![]);
+        vm.set_debug(true);
+        assert!(matches!(vm.run(), Err(RuntimeError { kind: ErrorKind::UnbalancedStack { expected: 0, found: 1 }, .. })));
+    }
+}